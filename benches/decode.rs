@@ -0,0 +1,48 @@
+//! Benchmarks for decoding RSV documents.
+//!
+//! Run with and without `--features memchr` to compare the two scanning strategies.
+
+use criterion::{black_box, criterion_group, criterion_main, Criterion, Throughput};
+use librsv::{decode_rsv_borrowed, encode_rsv, RsvReader};
+
+/// Generates a document with the given number of rows, containing a mixture of short and long values.
+fn document(rows: usize) -> Vec<u8> {
+    let data: Vec<Vec<Option<String>>> = (0..rows)
+        .map(|i| {
+            vec![
+                Some(i.to_string()),
+                Some(format!("user{i}@example.com")),
+                None,
+                Some("Lorem ipsum dolor sit amet, consectetur adipiscing elit".repeat(i % 4)),
+                Some("ünïcödé".into()),
+            ]
+        })
+        .collect();
+    encode_rsv(data)
+}
+
+fn bench_decode(c: &mut Criterion) {
+    let buffer = document(10_000);
+    let mut group = c.benchmark_group("decode");
+    group.throughput(Throughput::Bytes(buffer.len() as u64));
+
+    group.bench_function("rows", |b| {
+        b.iter(|| RsvReader::new(black_box(&buffer)).rows().count())
+    });
+    group.bench_function("values", |b| {
+        b.iter(|| {
+            RsvReader::new(black_box(&buffer))
+                .rows()
+                .map(|row| row.unwrap().values().count())
+                .sum::<usize>()
+        })
+    });
+    group.bench_function("decode_rsv_borrowed", |b| {
+        b.iter(|| decode_rsv_borrowed(black_box(&buffer)).unwrap())
+    });
+
+    group.finish();
+}
+
+criterion_group!(benches, bench_decode);
+criterion_main!(benches);
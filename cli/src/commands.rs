@@ -0,0 +1,243 @@
+//! The implementations of each subcommand.
+
+use crate::CliError;
+use librsv::{RsvStreamReader, RsvStreamWriter};
+use std::collections::VecDeque;
+use std::io::{BufRead, Write};
+use std::process::ExitCode;
+
+/// How null values are displayed by `cat`.
+const NULL_DISPLAY: &str = "∅";
+
+type Row = Vec<Option<String>>;
+
+/// Prints the document as a table with aligned columns.
+pub fn cat(input: impl BufRead, output: &mut impl Write) -> Result<(), CliError> {
+    let rows = RsvStreamReader::new(input)
+        .map(|row| row.map(|row| row.iter().map(display).collect()))
+        .collect::<Result<Vec<Vec<String>>, _>>()?;
+    write_table(output, &rows)?;
+    Ok(())
+}
+
+/// Formats a value for display in a table, escaping characters which would break the layout.
+fn display(value: &Option<String>) -> String {
+    match value {
+        Some(value) => value
+            .chars()
+            .map(|c| match c.is_control() {
+                true => c.escape_debug().to_string(),
+                false => c.to_string(),
+            })
+            .collect(),
+        None => NULL_DISPLAY.to_string(),
+    }
+}
+
+fn write_table(output: &mut impl Write, rows: &[Vec<String>]) -> std::io::Result<()> {
+    let mut widths = Vec::<usize>::new();
+    for row in rows {
+        if row.len() > widths.len() {
+            widths.resize(row.len(), 0);
+        }
+        for (width, value) in widths.iter_mut().zip(row) {
+            *width = (*width).max(value.chars().count());
+        }
+    }
+    for row in rows {
+        let mut line = String::new();
+        for (i, value) in row.iter().enumerate() {
+            if i > 0 {
+                line.push_str(" │ ");
+            }
+            line.push_str(value);
+            if i + 1 < row.len() {
+                let padding = widths[i] - value.chars().count();
+                line.push_str(&" ".repeat(padding));
+            }
+        }
+        writeln!(output, "{line}")?;
+    }
+    Ok(())
+}
+
+/// Checks that the document is valid, printing the number of rows or the location of the first error.
+pub fn validate(input: impl BufRead, output: &mut impl Write) -> Result<ExitCode, CliError> {
+    let mut reader = RsvStreamReader::new(input);
+    let mut count = 0;
+    loop {
+        let result = reader.next_row().and_then(|row| match row {
+            Some(row) => row.values().try_for_each(|v| v.map(drop)).map(|_| true),
+            None => Ok(false),
+        });
+        match result {
+            Ok(true) => count += 1,
+            Ok(false) => break,
+            Err(err @ librsv::Error::Io(_)) => return Err(err.into()),
+            Err(err) => {
+                eprintln!("rsv: invalid RSV: {err}");
+                return Ok(ExitCode::FAILURE);
+            }
+        }
+    }
+    writeln!(output, "valid: {count} rows")?;
+    Ok(ExitCode::SUCCESS)
+}
+
+/// Prints the number of rows in the document.
+pub fn count(input: impl BufRead, output: &mut impl Write) -> Result<(), CliError> {
+    let mut reader = RsvStreamReader::new(input);
+    let mut count = 0usize;
+    while reader.next_row()?.is_some() {
+        count += 1;
+    }
+    writeln!(output, "{count}")?;
+    Ok(())
+}
+
+/// Outputs the first `n` rows of the document.
+pub fn head(input: impl BufRead, output: &mut impl Write, n: usize) -> Result<(), CliError> {
+    let rows = RsvStreamReader::new(input).take(n);
+    write_rows(output, rows)
+}
+
+/// Outputs the last `n` rows of the document.
+pub fn tail(input: impl BufRead, output: &mut impl Write, n: usize) -> Result<(), CliError> {
+    let mut rows = VecDeque::with_capacity(n);
+    for row in RsvStreamReader::new(input) {
+        let row = row?;
+        if n == 0 {
+            continue;
+        }
+        if rows.len() == n {
+            rows.pop_front();
+        }
+        rows.push_back(row);
+    }
+    write_rows(output, rows.into_iter().map(Ok::<_, CliError>))
+}
+
+/// Outputs the given columns of each row, which are either zero-based indices or names from the first row.
+///
+/// A row which is too short to have one of the columns is reported as an error, rather than
+/// output with a null in its place, which would be indistinguishable from a real null.
+pub fn select(input: impl BufRead, output: &mut impl Write, columns: &str) -> Result<(), CliError> {
+    let columns = columns.split(',').map(str::trim).collect::<Vec<_>>();
+    let mut rows = RsvStreamReader::new(input).peekable();
+    let header = match rows.peek() {
+        Some(Ok(row)) => row.clone(),
+        Some(Err(err)) => return Err(err.clone().into()),
+        // An empty document has no header to look names up in, and no rows to select from
+        None => return write_rows(output, std::iter::empty::<Result<Row, CliError>>()),
+    };
+    let indices = columns
+        .iter()
+        .map(|&column| {
+            column.parse::<usize>().or_else(|_| {
+                header
+                    .iter()
+                    .position(|name| name.as_deref() == Some(column))
+                    .ok_or_else(|| CliError::Usage(format!("unknown column: {column}")))
+            })
+        })
+        .collect::<Result<Vec<_>, _>>()?;
+    let rows = rows.enumerate().map(|(n, row)| {
+        let row = row?;
+        indices
+            .iter()
+            .map(|&column| {
+                let value = row.get(column).cloned();
+                value.ok_or(CliError::MissingColumn { row: n, column })
+            })
+            .collect()
+    });
+    write_rows(output, rows)
+}
+
+fn write_rows<E>(
+    output: &mut impl Write,
+    rows: impl Iterator<Item = Result<Row, E>>,
+) -> Result<(), CliError>
+where
+    CliError: From<E>,
+{
+    let mut writer = RsvStreamWriter::new(output);
+    for row in rows {
+        writer.write_row(row?)?;
+    }
+    writer.finish()?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use librsv::{decode_rsv, encode_rsv};
+
+    fn sample() -> Vec<u8> {
+        encode_rsv(vec![
+            vec![Some("name"), Some("age")],
+            vec![Some("Alice"), Some("30")],
+            vec![Some("Bob"), None],
+            vec![Some("Carol, \"C\"\nSmith")],
+        ])
+    }
+
+    fn run(f: impl FnOnce(&[u8], &mut Vec<u8>) -> Result<(), CliError>, input: &[u8]) -> Vec<u8> {
+        let mut output = vec![];
+        f(input, &mut output).unwrap();
+        output
+    }
+
+    #[test]
+    fn cat_aligns_columns() {
+        let output = run(|i, o| cat(i, o), &sample());
+        assert_eq!(
+            String::from_utf8(output).unwrap(),
+            "name              │ age\n\
+             Alice             │ 30\n\
+             Bob               │ ∅\n\
+             Carol, \"C\"\\nSmith\n"
+        );
+    }
+
+    #[test]
+    fn head_tail_and_count() {
+        let data = sample();
+        let rows = decode_rsv(&data).unwrap();
+        let head = run(|i, o| head(i, o, 2), &data);
+        assert_eq!(decode_rsv(&head).unwrap(), &rows[..2]);
+        let tail = run(|i, o| tail(i, o, 3), &data);
+        assert_eq!(decode_rsv(&tail).unwrap(), &rows[1..]);
+        assert_eq!(run(|i, o| count(i, o), &data), b"4\n");
+    }
+
+    #[test]
+    fn select_by_index_and_name() {
+        let rows = decode_rsv(&sample()).unwrap();
+        let output = run(|i, o| select(i, o, "age,0"), &encode_rsv(&rows[..3]));
+        assert_eq!(
+            decode_rsv(&output).unwrap(),
+            vec![
+                vec![Some("age".into()), Some("name".into())],
+                vec![Some("30".into()), Some("Alice".into())],
+                vec![None, Some("Bob".into())],
+            ]
+        );
+    }
+
+    #[test]
+    fn select_reports_reader_errors() {
+        let mut output = vec![];
+        let err = select(&b"name\xFF"[..], &mut output, "name").unwrap_err();
+        assert!(matches!(
+            err,
+            CliError::Rsv(librsv::Error::UnterminatedRow { .. })
+        ));
+        let err = select(&sample()[..], &mut output, "0,age").unwrap_err();
+        assert!(matches!(err, CliError::MissingColumn { row: 3, column: 1 }));
+        let err = select(&sample()[..], &mut output, "height").unwrap_err();
+        assert!(matches!(err, CliError::Usage(message) if message == "unknown column: height"));
+        assert_eq!(run(|i, o| select(i, o, "name"), b""), b"");
+    }
+}
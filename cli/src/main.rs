@@ -0,0 +1,276 @@
+//! A command-line tool for inspecting and converting RSV (Rows of String Values) files.
+
+use librsv::convert::csv::{csv_to_rsv, rsv_to_csv, CsvOptions, NullPolicy};
+use librsv::convert::json::{from_json, to_json, JsonMode};
+use std::fs::File;
+use std::io::{self, BufRead, BufReader, BufWriter, Write};
+use std::process::ExitCode;
+use thiserror::Error;
+
+mod commands;
+
+const USAGE: &str = "\
+Usage: rsv <COMMAND> [OPTIONS] [FILE]
+
+Reads from standard input if FILE is omitted or is `-`.
+
+Commands:
+  cat                  Print the document as a table
+  validate             Check that the document is valid RSV
+  count                Print the number of rows
+  head [-n N]          Output the first N rows as RSV (default 10)
+  tail [-n N]          Output the last N rows as RSV (default 10)
+  select <COLUMNS>     Output the given columns as RSV
+                       COLUMNS is a comma-separated list of zero-based indices,
+                       or names which are looked up in the first row
+  to-csv, to-tsv       Convert RSV to CSV or TSV
+  to-json              Convert RSV to a JSON array of arrays
+  from-csv, from-tsv   Convert CSV or TSV to RSV
+  from-json            Convert a JSON array of arrays to RSV
+
+Options:
+  -n, --lines <N>      The number of rows for `head` and `tail`
+      --null <VALUE>   Represent nulls in CSV or TSV as VALUE, rather than empty fields
+      --lenient        Convert JSON numbers and booleans to strings, rather than rejecting them
+  -h, --help           Print this message";
+
+/// An error which causes the tool to exit unsuccessfully.
+#[derive(Error, Debug)]
+pub enum CliError {
+    /// The command line arguments were invalid.
+    #[error("{0}")]
+    Usage(String),
+    #[error(transparent)]
+    Io(#[from] io::Error),
+    #[error("invalid RSV: {0}")]
+    Rsv(#[from] librsv::Error),
+    /// A row selected from had too few values for one of the selected columns.
+    #[error("row {row} has no column {column}")]
+    MissingColumn { row: usize, column: usize },
+    #[error(transparent)]
+    Convert(#[from] librsv::convert::Error),
+}
+
+impl CliError {
+    /// Whether the error was caused by the output being closed, such as when piping into `head`.
+    fn is_broken_pipe(&self) -> bool {
+        use librsv::convert::Error as ConvertError;
+        let kind = match self {
+            CliError::Io(err) | CliError::Convert(ConvertError::Io(err)) => err.kind(),
+            CliError::Rsv(librsv::Error::Io(err))
+            | CliError::Convert(ConvertError::Rsv(librsv::Error::Io(err))) => err.kind(),
+            _ => return false,
+        };
+        kind == io::ErrorKind::BrokenPipe
+    }
+}
+
+/// The parsed command line arguments.
+#[derive(Debug, PartialEq, Eq)]
+pub struct Args {
+    pub command: String,
+    pub lines: usize,
+    pub null: Option<String>,
+    pub lenient: bool,
+    pub columns: Option<String>,
+    pub file: Option<String>,
+}
+
+impl Args {
+    /// Parses the command line arguments, excluding the program name.
+    pub fn parse(args: &[String]) -> Result<Option<Self>, CliError> {
+        let mut args = args.iter();
+        let mut command = None;
+        let mut lines = 10;
+        let mut null = None;
+        let mut lenient = false;
+        let mut positional = vec![];
+        while let Some(arg) = args.next() {
+            match arg.as_str() {
+                "-h" | "--help" => return Ok(None),
+                "-n" | "--lines" => {
+                    let value = args
+                        .next()
+                        .ok_or_else(|| CliError::Usage(format!("{arg} requires a value")))?;
+                    lines = value.parse().map_err(|_| {
+                        CliError::Usage(format!("invalid number of lines: {value}"))
+                    })?;
+                }
+                "--null" => {
+                    let value = args
+                        .next()
+                        .ok_or_else(|| CliError::Usage(format!("{arg} requires a value")))?;
+                    null = Some(value.clone());
+                }
+                "--lenient" => lenient = true,
+                "-" => positional.push(arg.clone()),
+                flag if flag.starts_with('-') => {
+                    return Err(CliError::Usage(format!("unknown option: {flag}")))
+                }
+                _ if command.is_none() => command = Some(arg.clone()),
+                _ => positional.push(arg.clone()),
+            }
+        }
+
+        let Some(command) = command else {
+            return Ok(None);
+        };
+        let mut positional = positional.into_iter();
+        let columns = match command.as_str() {
+            "select" => Some(
+                positional
+                    .next()
+                    .ok_or_else(|| CliError::Usage("select requires a list of columns".into()))?,
+            ),
+            _ => None,
+        };
+        let file = positional.next().filter(|file| file != "-");
+        if let Some(extra) = positional.next() {
+            return Err(CliError::Usage(format!("unexpected argument: {extra}")));
+        }
+
+        Ok(Some(Self {
+            command,
+            lines,
+            null,
+            lenient,
+            columns,
+            file,
+        }))
+    }
+
+    /// The options for reading or writing CSV with the given delimiter.
+    pub fn csv_options(&self, delimiter: u8) -> CsvOptions {
+        let nulls = match &self.null {
+            Some(sentinel) => NullPolicy::Sentinel(sentinel.clone()),
+            None => NullPolicy::Empty,
+        };
+        CsvOptions::default().delimiter(delimiter).nulls(nulls)
+    }
+
+    /// How non-string JSON values are handled.
+    pub fn json_mode(&self) -> JsonMode {
+        match self.lenient {
+            true => JsonMode::Lenient,
+            false => JsonMode::Strict,
+        }
+    }
+
+    /// Opens the input file, or standard input if no file was given.
+    pub fn input(&self) -> Result<Box<dyn BufRead>, CliError> {
+        Ok(match &self.file {
+            Some(path) => Box::new(BufReader::new(File::open(path)?)),
+            None => Box::new(BufReader::new(io::stdin())),
+        })
+    }
+}
+
+fn run(args: &[String]) -> Result<ExitCode, CliError> {
+    let Some(args) = Args::parse(args)? else {
+        println!("{USAGE}");
+        return Ok(ExitCode::SUCCESS);
+    };
+
+    let input = args.input()?;
+    let stdout = io::stdout();
+    let mut output = BufWriter::new(stdout.lock());
+
+    match args.command.as_str() {
+        "cat" => commands::cat(input, &mut output)?,
+        "validate" => return commands::validate(input, &mut output),
+        "count" => commands::count(input, &mut output)?,
+        "head" => commands::head(input, &mut output, args.lines)?,
+        "tail" => commands::tail(input, &mut output, args.lines)?,
+        "select" => commands::select(input, &mut output, args.columns.as_deref().unwrap())?,
+        "to-csv" => rsv_to_csv(input, &mut output, &args.csv_options(b','))?,
+        "to-tsv" => rsv_to_csv(input, &mut output, &args.csv_options(b'\t'))?,
+        "to-json" => {
+            to_json(input, &mut output)?;
+            writeln!(output)?;
+        }
+        "from-csv" => csv_to_rsv(input, &mut output, &args.csv_options(b','))?,
+        "from-tsv" => csv_to_rsv(input, &mut output, &args.csv_options(b'\t'))?,
+        "from-json" => from_json(input, &mut output, args.json_mode())?,
+        command => return Err(CliError::Usage(format!("unknown command: {command}"))),
+    }
+
+    output.flush()?;
+    Ok(ExitCode::SUCCESS)
+}
+
+fn main() -> ExitCode {
+    let args = std::env::args().skip(1).collect::<Vec<_>>();
+    match run(&args) {
+        Ok(code) => code,
+        Err(err) if err.is_broken_pipe() => ExitCode::SUCCESS,
+        Err(CliError::Usage(msg)) => {
+            eprintln!("rsv: {msg}\n\n{USAGE}");
+            ExitCode::from(2)
+        }
+        Err(err) => {
+            eprintln!("rsv: {err}");
+            ExitCode::FAILURE
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn parse(args: &[&str]) -> Result<Option<Args>, CliError> {
+        Args::parse(&args.iter().map(|s| s.to_string()).collect::<Vec<_>>())
+    }
+
+    #[test]
+    fn parses_arguments() {
+        assert_eq!(
+            parse(&["head", "-n", "5", "data.rsv"]).unwrap(),
+            Some(Args {
+                command: "head".into(),
+                lines: 5,
+                null: None,
+                lenient: false,
+                columns: None,
+                file: Some("data.rsv".into()),
+            })
+        );
+        assert_eq!(
+            parse(&["select", "0,name", "-"]).unwrap(),
+            Some(Args {
+                command: "select".into(),
+                lines: 10,
+                null: None,
+                lenient: false,
+                columns: Some("0,name".into()),
+                file: None,
+            })
+        );
+        assert_eq!(
+            parse(&["to-csv", "--null", "NULL"]).unwrap(),
+            Some(Args {
+                command: "to-csv".into(),
+                lines: 10,
+                null: Some("NULL".into()),
+                lenient: false,
+                columns: None,
+                file: None,
+            })
+        );
+        assert!(parse(&["from-json", "--lenient"]).unwrap().unwrap().lenient);
+        assert_eq!(parse(&[]).unwrap(), None);
+        assert_eq!(parse(&["cat", "--help"]).unwrap(), None);
+    }
+
+    #[test]
+    fn rejects_bad_arguments() {
+        assert!(matches!(parse(&["select"]), Err(CliError::Usage(_))));
+        assert!(matches!(parse(&["head", "-n"]), Err(CliError::Usage(_))));
+        assert!(matches!(
+            parse(&["head", "-n", "x"]),
+            Err(CliError::Usage(_))
+        ));
+        assert!(matches!(parse(&["cat", "--foo"]), Err(CliError::Usage(_))));
+        assert!(matches!(parse(&["cat", "a", "b"]), Err(CliError::Usage(_))));
+    }
+}
@@ -0,0 +1,315 @@
+use crate::stream::invalid_input;
+use crate::{
+    check_bytes, scan, Error, OwnedRow, RsvRow, WriteError, END_ROW, END_VALUE, NULL_VALUE,
+};
+use futures_core::Stream;
+use std::future::poll_fn;
+use std::io;
+use std::pin::Pin;
+use std::task::{ready, Context, Poll};
+use tokio::io::{AsyncBufRead, AsyncWrite, AsyncWriteExt};
+
+/// Writes an RSV document to any `tokio::io::AsyncWrite` sink, such as a socket.
+///
+/// This mirrors `RsvStreamWriter`, except that the final row terminator is only written when
+/// `finish` is called, as it cannot be written when the writer is dropped.
+/// For unbuffered sinks, wrapping them in a `BufWriter` is recommended.
+///
+/// # Example:
+/// ```
+/// use librsv::AsyncRsvWriter;
+///
+/// # tokio::runtime::Builder::new_current_thread().build().unwrap().block_on(async {
+/// let mut writer = AsyncRsvWriter::new(Vec::new());
+/// writer.start_row().await?;
+/// writer.push_str("Hello").await?;
+/// writer.push_null().await?;
+/// let buffer = writer.finish().await?;
+///
+/// assert_eq!(&buffer, b"Hello\xFF\xFE\xFF\xFD");
+/// # Ok::<(), std::io::Error>(())
+/// # }).unwrap();
+/// ```
+pub struct AsyncRsvWriter<W: AsyncWrite + Unpin> {
+    inner: W,
+    started_row: bool,
+}
+
+impl<W: AsyncWrite + Unpin> AsyncRsvWriter<W> {
+    /// Creates a new `AsyncRsvWriter` which writes to the given sink.
+    pub fn new(inner: W) -> Self {
+        Self {
+            inner,
+            started_row: false,
+        }
+    }
+
+    /// Returns a reference to the underlying sink.
+    pub fn get_ref(&self) -> &W {
+        &self.inner
+    }
+
+    /// Returns a mutable reference to the underlying sink.
+    ///
+    /// Writing directly to the sink may corrupt the RSV document.
+    pub fn get_mut(&mut self) -> &mut W {
+        &mut self.inner
+    }
+
+    /// Begins a new row.
+    ///
+    /// This must be called before pushing any values.
+    pub async fn start_row(&mut self) -> io::Result<()> {
+        if self.started_row {
+            self.inner.write_all(&[END_ROW]).await?;
+        }
+        self.started_row = true;
+        Ok(())
+    }
+
+    /// Pushes a value to the current row.
+    ///
+    /// Returns an error if no row has been started, in the same way as `RsvStreamWriter::push`.
+    pub async fn push(&mut self, value: Option<&str>) -> io::Result<()> {
+        self.check_row()?;
+        match value {
+            Some(str) => self.inner.write_all(str.as_bytes()).await?,
+            None => self.inner.write_all(&[NULL_VALUE]).await?,
+        }
+        self.inner.write_all(&[END_VALUE]).await
+    }
+
+    /// Pushes a value of raw bytes to the current row, which need not be valid UTF-8.
+    ///
+    /// Values containing reserved bytes are rejected in the same way as by `RsvStreamWriter::push_bytes`.
+    pub async fn push_bytes(&mut self, value: Option<&[u8]>) -> io::Result<()> {
+        if let Some(bytes) = value {
+            check_bytes(bytes).map_err(invalid_input)?;
+        }
+        self.check_row()?;
+        match value {
+            Some(bytes) => self.inner.write_all(bytes).await?,
+            None => self.inner.write_all(&[NULL_VALUE]).await?,
+        }
+        self.inner.write_all(&[END_VALUE]).await
+    }
+
+    /// Pushes a string value to the current row.
+    pub async fn push_str(&mut self, value: &str) -> io::Result<()> {
+        self.push(Some(value)).await
+    }
+
+    /// Pushes an empty value to the current row.
+    pub async fn push_null(&mut self) -> io::Result<()> {
+        self.push(None).await
+    }
+
+    /// Flushes the underlying sink.
+    ///
+    /// This does not terminate the current row.
+    pub async fn flush(&mut self) -> io::Result<()> {
+        self.inner.flush().await
+    }
+
+    /// Terminates the current row, if any, flushes the sink, and returns it.
+    pub async fn finish(mut self) -> io::Result<W> {
+        if self.started_row {
+            self.inner.write_all(&[END_ROW]).await?;
+        }
+        self.inner.flush().await?;
+        Ok(self.inner)
+    }
+
+    /// Checks that there is a row to push values to.
+    fn check_row(&self) -> io::Result<()> {
+        match self.started_row {
+            true => Ok(()),
+            false => Err(invalid_input(WriteError::RowNotStarted)),
+        }
+    }
+}
+
+/// Reads an RSV document incrementally from any `tokio::io::AsyncBufRead` source.
+///
+/// This mirrors `RsvStreamReader`, except that rows are read as `OwnedRow`s, either with `next_row`
+/// or by using the reader as a `Stream`.
+///
+/// # Example:
+/// ```
+/// use librsv::AsyncRsvReader;
+///
+/// # tokio::runtime::Builder::new_current_thread().build().unwrap().block_on(async {
+/// let input: &[u8] = b"Hello\xFFworld\xFF\xFD\xFE\xFF\xFD";
+/// let mut reader = AsyncRsvReader::new(input);
+///
+/// while let Some(row) = reader.next_row().await? {
+///     for value in row.iter() {
+///         println!("{:?}", value);
+///     }
+/// }
+/// # Ok::<(), librsv::Error>(())
+/// # }).unwrap();
+/// ```
+pub struct AsyncRsvReader<R: AsyncBufRead + Unpin> {
+    inner: R,
+    buffer: Vec<u8>,
+    offset: usize,
+    index: usize,
+}
+
+impl<R: AsyncBufRead + Unpin> AsyncRsvReader<R> {
+    /// Creates a new `AsyncRsvReader` which reads from the given source.
+    pub fn new(inner: R) -> Self {
+        Self {
+            inner,
+            buffer: Vec::new(),
+            offset: 0,
+            index: 0,
+        }
+    }
+
+    /// Reads the next row, returning `None` once the end of the stream is reached.
+    ///
+    /// If the stream ends part way through a row, `Error::UnterminatedRow` is returned.
+    pub async fn next_row(&mut self) -> Result<Option<OwnedRow>, Error> {
+        poll_fn(|cx| self.poll_next_row(cx)).await
+    }
+
+    fn poll_next_row(&mut self, cx: &mut Context<'_>) -> Poll<Result<Option<OwnedRow>, Error>> {
+        loop {
+            let available = ready!(Pin::new(&mut self.inner).poll_fill_buf(cx))?;
+            if available.is_empty() {
+                if self.buffer.is_empty() {
+                    return Poll::Ready(Ok(None));
+                }
+                self.buffer.clear();
+                return Poll::Ready(Err(Error::UnterminatedRow {
+                    offset: self.offset,
+                    row: self.index,
+                }));
+            }
+
+            // Partial rows are kept in the buffer, so no data is lost if the source is not ready
+            let Some(terminator) = scan::find(END_ROW, available) else {
+                let len = available.len();
+                self.buffer.extend_from_slice(available);
+                Pin::new(&mut self.inner).consume(len);
+                continue;
+            };
+            self.buffer.extend_from_slice(&available[..terminator]);
+            Pin::new(&mut self.inner).consume(terminator + 1);
+
+            let row = RsvRow::at(&self.buffer, self.offset, self.index);
+            let row = OwnedRow::from_row(&row);
+            self.offset += self.buffer.len() + 1;
+            self.index += 1;
+            self.buffer.clear();
+            return Poll::Ready(row.map(Some));
+        }
+    }
+
+    /// Returns a reference to the underlying source.
+    pub fn get_ref(&self) -> &R {
+        &self.inner
+    }
+
+    /// Returns a mutable reference to the underlying source.
+    ///
+    /// Reading directly from the source may cause rows to be skipped or truncated.
+    pub fn get_mut(&mut self) -> &mut R {
+        &mut self.inner
+    }
+
+    /// Consumes the reader, returning the underlying source.
+    pub fn into_inner(self) -> R {
+        self.inner
+    }
+}
+
+impl<R: AsyncBufRead + Unpin> Stream for AsyncRsvReader<R> {
+    type Item = Result<OwnedRow, Error>;
+
+    fn poll_next(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
+        self.get_mut().poll_next_row(cx).map(Result::transpose)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{decode_rsv, RsvWriter};
+    use tokio::io::BufReader;
+
+    fn block_on<F: std::future::Future>(future: F) -> F::Output {
+        tokio::runtime::Builder::new_current_thread()
+            .build()
+            .unwrap()
+            .block_on(future)
+    }
+
+    async fn collect<R: AsyncBufRead + Unpin>(
+        mut reader: AsyncRsvReader<R>,
+    ) -> Result<Vec<Vec<Option<String>>>, Error> {
+        let mut rows = vec![];
+        while let Some(row) = poll_fn(|cx| Pin::new(&mut reader).poll_next(cx)).await {
+            rows.push(row?.iter().map(|v| v.map(String::from)).collect());
+        }
+        Ok(rows)
+    }
+
+    #[test]
+    fn matches_rsv_writer() {
+        let mut a = RsvWriter::new();
+        a.start_row();
+        a.push_str("Hello");
+        a.push_null();
+        a.start_row();
+        a.start_row();
+        a.push_bytes(Some(b"\xE9")).unwrap();
+
+        let b = block_on(async {
+            let mut b = AsyncRsvWriter::new(Vec::new());
+            let err = b.push_null().await.unwrap_err();
+            assert_eq!(err.kind(), io::ErrorKind::InvalidInput);
+            b.start_row().await?;
+            b.push_str("Hello").await?;
+            b.push_null().await?;
+            b.start_row().await?;
+            b.start_row().await?;
+            b.push_bytes(Some(b"\xE9")).await?;
+            assert!(b.push_bytes(Some(b"\xFD")).await.is_err());
+            b.finish().await
+        });
+        assert_eq!(a.finish(), b.unwrap());
+    }
+
+    #[test]
+    fn reads_rows_incrementally() {
+        let data = b"Hello\xFFworld\xFF\xFD\xFD\xFE\xFF\xFF\xFD";
+        // A tiny buffer capacity forces rows to span multiple reads
+        let reader = AsyncRsvReader::new(BufReader::with_capacity(3, &data[..]));
+        let rows = block_on(collect(reader)).unwrap();
+        assert_eq!(rows, decode_rsv(data).unwrap());
+    }
+
+    #[test]
+    fn reports_errors() {
+        block_on(async {
+            let mut reader = AsyncRsvReader::new(&b"a\xFF\xFDb\xC3\xFF\xFDc\xFF"[..]);
+            assert!(reader.next_row().await.unwrap().is_some());
+            assert!(matches!(
+                reader.next_row().await,
+                Err(Error::BadUTF8 {
+                    offset: 4,
+                    row: 1,
+                    ..
+                })
+            ));
+            assert!(matches!(
+                reader.next_row().await,
+                Err(Error::UnterminatedRow { offset: 7, row: 2 })
+            ));
+            assert!(reader.next_row().await.unwrap().is_none());
+        });
+    }
+}
@@ -0,0 +1,588 @@
+//! Conversion between RSV and CSV, as described by [RFC 4180](https://www.rfc-editor.org/rfc/rfc4180).
+//!
+//! Fields may be quoted, in which case they can contain delimiters, line breaks and escaped quotes (`""`).
+//! Records may be terminated by either `\n` or `\r\n`, and records are written with `\n`.
+//!
+//! CSV has no concept of a null value, so a `NullPolicy` determines how RSV nulls are represented.
+//!
+//! # Example:
+//! ```
+//! use librsv::convert::csv::{csv_to_rsv, rsv_to_csv, CsvOptions};
+//!
+//! let csv = "name,age\nAlice,30\n\"Smith, Bob\",\n";
+//!
+//! let mut rsv = Vec::new();
+//! csv_to_rsv(csv.as_bytes(), &mut rsv, &CsvOptions::default())?;
+//! assert_eq!(&rsv, b"name\xFFage\xFF\xFDAlice\xFF30\xFF\xFDSmith, Bob\xFF\xFE\xFF\xFD");
+//!
+//! let mut output = Vec::new();
+//! rsv_to_csv(&rsv[..], &mut output, &CsvOptions::default())?;
+//! assert_eq!(output, csv.as_bytes());
+//! # Ok::<(), librsv::convert::Error>(())
+//! ```
+
+use super::Error;
+use crate::{RsvStreamReader, RsvStreamWriter};
+use std::fmt;
+use std::io::{BufRead, Write};
+
+/// How null values are represented in CSV.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum NullPolicy {
+    /// Nulls are written as empty, unquoted fields, and empty strings are written as `""`.
+    ///
+    /// A row containing only a null would be written as an empty line, which is read back as an
+    /// empty row, so writing one is an error. When reading, empty unquoted fields are read as null.
+    Empty,
+    /// Nulls are written as the given string, unquoted. Strings equal to the sentinel are quoted.
+    ///
+    /// When reading, unquoted fields equal to the sentinel are read as null.
+    Sentinel(String),
+    /// Writing a null is an error. When reading, no fields are read as null.
+    Error,
+}
+
+/// Options controlling how CSV is read and written.
+#[derive(Clone, Debug)]
+pub struct CsvOptions {
+    delimiter: u8,
+    quote: u8,
+    nulls: NullPolicy,
+}
+
+impl Default for CsvOptions {
+    /// Comma delimited, with double quotes and `NullPolicy::Empty`.
+    fn default() -> Self {
+        Self {
+            delimiter: b',',
+            quote: b'"',
+            nulls: NullPolicy::Empty,
+        }
+    }
+}
+
+impl CsvOptions {
+    /// Sets the field delimiter, which defaults to `,`.
+    ///
+    /// Panics if the delimiter is not an ASCII character, or is a line break or the quote character.
+    pub fn delimiter(mut self, delimiter: u8) -> Self {
+        assert!(
+            delimiter.is_ascii() && !matches!(delimiter, b'\r' | b'\n') && delimiter != self.quote,
+            "invalid CSV delimiter"
+        );
+        self.delimiter = delimiter;
+        self
+    }
+
+    /// Sets the quote character, which defaults to `"`.
+    ///
+    /// Panics if the quote is not an ASCII character, or is a line break or the delimiter.
+    pub fn quote(mut self, quote: u8) -> Self {
+        assert!(
+            quote.is_ascii() && !matches!(quote, b'\r' | b'\n') && quote != self.delimiter,
+            "invalid CSV quote character"
+        );
+        self.quote = quote;
+        self
+    }
+
+    /// Sets how null values are represented, which defaults to `NullPolicy::Empty`.
+    pub fn nulls(mut self, nulls: NullPolicy) -> Self {
+        self.nulls = nulls;
+        self
+    }
+
+    /// Whether an unquoted field with the given value represents a null.
+    fn is_null(&self, value: &[u8]) -> bool {
+        match &self.nulls {
+            NullPolicy::Empty => value.is_empty(),
+            NullPolicy::Sentinel(sentinel) => value == sentinel.as_bytes(),
+            NullPolicy::Error => false,
+        }
+    }
+}
+
+/// What was wrong with a CSV input.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum CsvErrorKind {
+    /// The input ended inside a quoted field.
+    UnterminatedQuote,
+    /// A quoted field was followed by something other than a delimiter or line break.
+    UnexpectedCharacter,
+    /// A field contained invalid UTF-8.
+    BadUTF8,
+}
+
+impl fmt::Display for CsvErrorKind {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            CsvErrorKind::UnterminatedQuote => "unexpected end of input in a quoted field",
+            CsvErrorKind::UnexpectedCharacter => "unexpected character after a quoted field",
+            CsvErrorKind::BadUTF8 => "a field contained invalid UTF-8",
+        })
+    }
+}
+
+/// Converts a CSV document to RSV.
+pub fn csv_to_rsv<R: BufRead, W: Write>(
+    input: R,
+    output: W,
+    options: &CsvOptions,
+) -> Result<(), Error> {
+    let mut writer = RsvStreamWriter::new(output);
+    for record in CsvReader::new(input, options.clone()) {
+        let record = record?;
+        writer.start_row()?;
+        for value in record {
+            writer.push(value.as_deref())?;
+        }
+    }
+    writer.finish()?;
+    Ok(())
+}
+
+/// Converts an RSV document to CSV.
+pub fn rsv_to_csv<R: BufRead, W: Write>(
+    input: R,
+    output: W,
+    options: &CsvOptions,
+) -> Result<(), Error> {
+    let mut reader = RsvStreamReader::new(input);
+    let mut writer = CsvWriter::new(output, options.clone());
+    while let Some(row) = reader.next_row()? {
+        let values = row.values().collect::<Result<Vec<_>, _>>()?;
+        writer.write_record(values)?;
+    }
+    writer.flush()?;
+    Ok(())
+}
+
+/// The state of the CSV parser within a record.
+#[derive(Clone, Copy, PartialEq, Eq)]
+enum State {
+    /// At the start of a field.
+    Start,
+    /// Within an unquoted field.
+    Unquoted,
+    /// Within a quoted field.
+    Quoted,
+    /// Immediately after a quote within a quoted field.
+    QuotedEnd,
+}
+
+/// Reads CSV records from a `std::io::BufRead` source.
+///
+/// Each record is read as a `Vec<Option<String>>`, with nulls determined by the `NullPolicy`.
+/// An empty line is read as a record with no fields.
+pub struct CsvReader<R: BufRead> {
+    inner: R,
+    options: CsvOptions,
+    offset: usize,
+    line: usize,
+    /// Whether a `\r` ended the last record, so that a following `\n` should be skipped.
+    skip_lf: bool,
+}
+
+impl<R: BufRead> CsvReader<R> {
+    /// Creates a new `CsvReader` which reads from the given source.
+    pub fn new(inner: R, options: CsvOptions) -> Self {
+        Self {
+            inner,
+            options,
+            offset: 0,
+            line: 1,
+            skip_lf: false,
+        }
+    }
+
+    /// Reads the next record, returning `None` at the end of the input.
+    pub fn read_record(&mut self) -> Result<Option<Vec<Option<String>>>, Error> {
+        if std::mem::take(&mut self.skip_lf) && self.inner.fill_buf()?.first() == Some(&b'\n') {
+            self.inner.consume(1);
+            self.offset += 1;
+        }
+
+        let mut record = vec![];
+        let mut field = vec![];
+        // The positions in `field` of quotes which were escaped by doubling them
+        let mut escapes = vec![];
+        let mut state = State::Start;
+        let mut at_start = true;
+        // The position of the start of the current field, for reporting errors
+        let mut field_start = (self.offset, self.line);
+
+        loop {
+            let buffer = self.inner.fill_buf()?;
+            if buffer.is_empty() {
+                return match state {
+                    State::Start if at_start => Ok(None),
+                    State::Quoted => Err(Error::Csv {
+                        offset: field_start.0,
+                        line: field_start.1,
+                        kind: CsvErrorKind::UnterminatedQuote,
+                    }),
+                    _ => {
+                        let field = (&mut field, &mut escapes);
+                        finish_field(&self.options, &mut record, field, state, field_start)?;
+                        Ok(Some(record))
+                    }
+                };
+            }
+
+            // Parse as much of the buffer as possible, stopping at the end of the record
+            let mut consumed = 0;
+            let mut outcome = None;
+            for &byte in buffer {
+                let offset = self.offset + consumed;
+                consumed += 1;
+                let line_break = byte == b'\n' || byte == b'\r';
+                match state {
+                    State::Quoted if byte == self.options.quote => state = State::QuotedEnd,
+                    State::Quoted => {
+                        self.line += (byte == b'\n') as usize;
+                        field.push(byte);
+                    }
+                    State::QuotedEnd if byte == self.options.quote => {
+                        escapes.push(field.len());
+                        field.push(byte);
+                        state = State::Quoted;
+                    }
+                    State::Start if byte == self.options.quote => {
+                        state = State::Quoted;
+                        at_start = false;
+                    }
+                    _ if byte == self.options.delimiter || line_break => {
+                        if !(line_break && at_start) {
+                            let result = finish_field(
+                                &self.options,
+                                &mut record,
+                                (&mut field, &mut escapes),
+                                state,
+                                field_start,
+                            );
+                            if result.is_err() {
+                                outcome = Some(result);
+                                break;
+                            }
+                        }
+                        if line_break {
+                            self.line += 1;
+                            self.skip_lf = byte == b'\r';
+                            outcome = Some(Ok(()));
+                            break;
+                        }
+                        state = State::Start;
+                        at_start = false;
+                        field_start = (offset + 1, self.line);
+                    }
+                    State::QuotedEnd => {
+                        outcome = Some(Err(Error::Csv {
+                            offset,
+                            line: self.line,
+                            kind: CsvErrorKind::UnexpectedCharacter,
+                        }));
+                        break;
+                    }
+                    State::Start | State::Unquoted => {
+                        field.push(byte);
+                        state = State::Unquoted;
+                        at_start = false;
+                    }
+                }
+            }
+            self.inner.consume(consumed);
+            self.offset += consumed;
+
+            match outcome {
+                Some(Ok(())) => return Ok(Some(record)),
+                Some(Err(err)) => return Err(err),
+                None => {}
+            }
+        }
+    }
+}
+
+/// Converts the bytes of a completed field into a value, and adds it to the record.
+///
+/// The field is given along with the positions of its escaped quotes, and both are cleared.
+fn finish_field(
+    options: &CsvOptions,
+    record: &mut Vec<Option<String>>,
+    (field, escapes): (&mut Vec<u8>, &mut Vec<usize>),
+    state: State,
+    (offset, line): (usize, usize),
+) -> Result<(), Error> {
+    let bytes = std::mem::take(field);
+    let escapes = std::mem::take(escapes);
+    let quoted = state == State::QuotedEnd;
+    if !quoted && options.is_null(&bytes) {
+        record.push(None);
+        return Ok(());
+    }
+    let value = String::from_utf8(bytes).map_err(|err| {
+        // Map the position within the field back to the input, which also contains the opening
+        // quote and the first quote of each escaped pair
+        let valid = err.utf8_error().valid_up_to();
+        let escaped = escapes.iter().filter(|&&i| i < valid).count();
+        let lines = err.as_bytes()[..valid]
+            .iter()
+            .filter(|&&b| b == b'\n')
+            .count();
+        Error::Csv {
+            offset: offset + usize::from(quoted) + escaped + valid,
+            line: line + lines,
+            kind: CsvErrorKind::BadUTF8,
+        }
+    })?;
+    record.push(Some(value));
+    Ok(())
+}
+
+impl<R: BufRead> Iterator for CsvReader<R> {
+    type Item = Result<Vec<Option<String>>, Error>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        self.read_record().transpose()
+    }
+}
+
+/// Writes CSV records to a `std::io::Write` sink.
+pub struct CsvWriter<W: Write> {
+    inner: W,
+    options: CsvOptions,
+    row: usize,
+}
+
+impl<W: Write> CsvWriter<W> {
+    /// Creates a new `CsvWriter` which writes to the given sink.
+    pub fn new(inner: W, options: CsvOptions) -> Self {
+        Self {
+            inner,
+            options,
+            row: 0,
+        }
+    }
+
+    /// Writes a record, quoting fields where necessary.
+    ///
+    /// Null values are written according to the `NullPolicy`.
+    pub fn write_record<'a>(
+        &mut self,
+        values: impl IntoIterator<Item = Option<&'a str>>,
+    ) -> Result<(), Error> {
+        let mut values = values.into_iter().enumerate().peekable();
+        while let Some((i, value)) = values.next() {
+            if i > 0 {
+                self.inner.write_all(&[self.options.delimiter])?;
+            }
+            let Some(value) = value else {
+                match &self.options.nulls {
+                    NullPolicy::Empty if i == 0 && values.peek().is_none() => {
+                        return Err(Error::UnexpectedNull {
+                            row: self.row,
+                            value: i,
+                        })
+                    }
+                    NullPolicy::Empty => {}
+                    NullPolicy::Sentinel(sentinel) => self.inner.write_all(sentinel.as_bytes())?,
+                    NullPolicy::Error => {
+                        return Err(Error::UnexpectedNull {
+                            row: self.row,
+                            value: i,
+                        })
+                    }
+                }
+                continue;
+            };
+            // A lone empty field must be quoted, or it would be read as an empty record
+            let lone_empty = i == 0 && value.is_empty() && values.peek().is_none();
+            if lone_empty || self.needs_quotes(value) {
+                self.write_quoted(value)?;
+            } else {
+                self.inner.write_all(value.as_bytes())?;
+            }
+        }
+        self.inner.write_all(b"\n")?;
+        self.row += 1;
+        Ok(())
+    }
+
+    /// Flushes the underlying sink.
+    pub fn flush(&mut self) -> Result<(), Error> {
+        self.inner.flush()?;
+        Ok(())
+    }
+
+    /// Consumes the writer, returning the underlying sink.
+    pub fn into_inner(self) -> W {
+        self.inner
+    }
+
+    fn needs_quotes(&self, value: &str) -> bool {
+        let special = [self.options.delimiter, self.options.quote, b'\n', b'\r'];
+        value.bytes().any(|b| special.contains(&b)) || self.options.is_null(value.as_bytes())
+    }
+
+    fn write_quoted(&mut self, value: &str) -> Result<(), Error> {
+        let quote = self.options.quote;
+        self.inner.write_all(&[quote])?;
+        for (i, part) in value.split(quote as char).enumerate() {
+            if i > 0 {
+                self.inner.write_all(&[quote, quote])?;
+            }
+            self.inner.write_all(part.as_bytes())?;
+        }
+        self.inner.write_all(&[quote])?;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{decode_rsv, encode_rsv};
+
+    fn read(csv: &str, options: CsvOptions) -> Result<Vec<Vec<Option<String>>>, Error> {
+        CsvReader::new(csv.as_bytes(), options).collect()
+    }
+
+    fn write(rows: &[Vec<Option<&str>>], options: CsvOptions) -> Result<String, Error> {
+        let mut writer = CsvWriter::new(vec![], options);
+        for row in rows {
+            writer.write_record(row.iter().copied())?;
+        }
+        Ok(String::from_utf8(writer.into_inner()).unwrap())
+    }
+
+    fn s(value: &str) -> Option<String> {
+        Some(value.to_string())
+    }
+
+    #[test]
+    fn reads_rfc_4180() {
+        let csv = "a,\"b,c\",\"say \"\"hi\"\"\"\r\n\"multi\nline\",,\"\"\n\nlast";
+        assert_eq!(
+            read(csv, CsvOptions::default()).unwrap(),
+            vec![
+                vec![s("a"), s("b,c"), s("say \"hi\"")],
+                vec![s("multi\nline"), None, s("")],
+                vec![],
+                vec![s("last")],
+            ]
+        );
+    }
+
+    #[test]
+    fn reads_across_buffer_boundaries() {
+        let csv = "a,\"b\"\"c\"\r\n\"d\ne\",f\r\n";
+        let expected = read(csv, CsvOptions::default()).unwrap();
+        let input = std::io::BufReader::with_capacity(1, csv.as_bytes());
+        let actual = CsvReader::new(input, CsvOptions::default())
+            .collect::<Result<Vec<_>, _>>()
+            .unwrap();
+        assert_eq!(actual, expected);
+        assert_eq!(actual.len(), 2);
+    }
+
+    #[test]
+    fn custom_delimiter_quote_and_nulls() {
+        let options = CsvOptions::default()
+            .delimiter(b';')
+            .quote(b'\'')
+            .nulls(NullPolicy::Sentinel("NULL".into()));
+        let csv = "a;'b;c';NULL;'NULL'\n";
+        let rows = read(csv, options.clone()).unwrap();
+        assert_eq!(rows, vec![vec![s("a"), s("b;c"), None, s("NULL")]]);
+        let rows = [vec![Some("a"), Some("b;c"), None, Some("NULL")]];
+        assert_eq!(write(&rows, options).unwrap(), csv);
+    }
+
+    #[test]
+    fn null_policies() {
+        let rows = [vec![Some(""), None], vec![Some("")]];
+        assert_eq!(
+            write(&rows, CsvOptions::default()).unwrap(),
+            "\"\",\n\"\"\n"
+        );
+
+        let options = CsvOptions::default().nulls(NullPolicy::Error);
+        assert!(matches!(
+            write(&rows, options.clone()),
+            Err(Error::UnexpectedNull { row: 0, value: 1 })
+        ));
+        assert_eq!(read(",\n", options).unwrap(), vec![vec![s(""), s("")]]);
+    }
+
+    #[test]
+    fn reports_errors() {
+        let err = read("a\n\"b\nc", CsvOptions::default()).unwrap_err();
+        assert!(matches!(
+            err,
+            Error::Csv {
+                offset: 2,
+                line: 2,
+                kind: CsvErrorKind::UnterminatedQuote
+            }
+        ));
+
+        let err = read("a,\"b\"c\n", CsvOptions::default()).unwrap_err();
+        assert!(matches!(
+            err,
+            Error::Csv {
+                offset: 5,
+                line: 1,
+                kind: CsvErrorKind::UnexpectedCharacter
+            }
+        ));
+
+        let mut reader = CsvReader::new(&b"ab\xC3\n"[..], CsvOptions::default());
+        assert!(matches!(
+            reader.next(),
+            Some(Err(Error::Csv {
+                offset: 2,
+                kind: CsvErrorKind::BadUTF8,
+                ..
+            }))
+        ));
+
+        let mut reader = CsvReader::new(&b"x,\"a\"\"\n\"\"b\xC3\"\n"[..], CsvOptions::default());
+        assert!(matches!(
+            reader.next(),
+            Some(Err(Error::Csv {
+                offset: 10,
+                line: 2,
+                kind: CsvErrorKind::BadUTF8,
+            }))
+        ));
+    }
+
+    #[test]
+    fn roundtrip() {
+        let data = vec![
+            vec![Some("name"), Some("note")],
+            vec![Some("Alice"), Some("says \"hi\",\r\nthen leaves")],
+            vec![Some(""), None],
+            vec![],
+            vec![Some("")],
+            vec![None],
+        ];
+        let rsv = encode_rsv(&data);
+        let roundtrip = |rsv: &[u8], options: &CsvOptions| {
+            let mut csv = vec![];
+            rsv_to_csv(rsv, &mut csv, options)?;
+            let mut output = vec![];
+            csv_to_rsv(&csv[..], &mut output, options)?;
+            assert_eq!(decode_rsv(&output).unwrap(), decode_rsv(rsv).unwrap());
+            Ok::<_, Error>(())
+        };
+
+        let sentinel = CsvOptions::default().nulls(NullPolicy::Sentinel("NULL".into()));
+        roundtrip(&rsv, &sentinel).unwrap();
+        // A lone null would be read back as an empty row, so is rejected rather than lost
+        assert!(matches!(
+            roundtrip(&rsv, &CsvOptions::default()),
+            Err(Error::UnexpectedNull { row: 5, value: 0 })
+        ));
+        roundtrip(&encode_rsv(&data[..5]), &CsvOptions::default()).unwrap();
+    }
+}
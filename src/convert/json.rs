@@ -0,0 +1,478 @@
+//! Conversion between RSV and JSON.
+//!
+//! The RSV specification describes a document as equivalent to a JSON array of arrays of strings
+//! or nulls (`(string | null)[][]` in TypeScript terms). Both directions are streamed, so neither
+//! document is ever held in memory.
+//!
+//! # Example:
+//! ```
+//! use librsv::convert::json::{from_json, to_json, JsonMode};
+//!
+//! let json = r#"[["Hello", "world"], [null, ""], []]"#;
+//!
+//! let mut rsv = Vec::new();
+//! from_json(json.as_bytes(), &mut rsv, JsonMode::Strict)?;
+//! assert_eq!(&rsv, b"Hello\xFFworld\xFF\xFD\xFE\xFF\xFF\xFD\xFD");
+//!
+//! let mut output = Vec::new();
+//! to_json(&rsv[..], &mut output)?;
+//! assert_eq!(output, b"[\n[\"Hello\",\"world\"],\n[null,\"\"],\n[]\n]");
+//! # Ok::<(), librsv::convert::Error>(())
+//! ```
+
+use super::Error;
+use crate::{RsvStreamReader, RsvStreamWriter};
+use std::fmt;
+use std::io::{BufRead, Write};
+
+/// How non-string JSON scalars are handled when converting JSON to RSV.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum JsonMode {
+    /// Numbers and booleans are rejected with an error.
+    Strict,
+    /// Numbers and booleans are converted to strings, exactly as they appear in the JSON.
+    Lenient,
+}
+
+/// What was wrong with a JSON input.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum JsonErrorKind {
+    /// The input ended unexpectedly.
+    UnexpectedEnd,
+    /// The input contained an unexpected character.
+    UnexpectedCharacter,
+    /// A string contained an invalid escape sequence or control character.
+    InvalidString,
+    /// A number was not formatted correctly.
+    InvalidNumber,
+    /// A string contained invalid UTF-8.
+    BadUTF8,
+    /// A number or boolean was found in strict mode.
+    NonStringValue,
+    /// An array or object was found where a string or null was expected.
+    NestedValue,
+}
+
+impl fmt::Display for JsonErrorKind {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            JsonErrorKind::UnexpectedEnd => "unexpected end of input",
+            JsonErrorKind::UnexpectedCharacter => "unexpected character",
+            JsonErrorKind::InvalidString => {
+                "invalid escape sequence or control character in string"
+            }
+            JsonErrorKind::InvalidNumber => "invalid number",
+            JsonErrorKind::BadUTF8 => "a string contained invalid UTF-8",
+            JsonErrorKind::NonStringValue => "expected a string or null",
+            JsonErrorKind::NestedValue => "values cannot be arrays or objects",
+        })
+    }
+}
+
+/// Converts an RSV document to a JSON array of arrays, with one row per line.
+pub fn to_json<R: BufRead, W: Write>(input: R, mut output: W) -> Result<(), Error> {
+    let mut reader = RsvStreamReader::new(input);
+    let mut first = true;
+    output.write_all(b"[")?;
+    while let Some(row) = reader.next_row()? {
+        output.write_all(if first { b"\n[" } else { b",\n[" })?;
+        first = false;
+        for (i, value) in row.values().enumerate() {
+            if i > 0 {
+                output.write_all(b",")?;
+            }
+            match value? {
+                Some(value) => write_string(&mut output, value)?,
+                None => output.write_all(b"null")?,
+            }
+        }
+        output.write_all(b"]")?;
+    }
+    output.write_all(if first { b"]" } else { b"\n]" })?;
+    output.flush()?;
+    Ok(())
+}
+
+/// Writes a JSON string literal, escaping characters as required.
+fn write_string<W: Write>(output: &mut W, value: &str) -> Result<(), Error> {
+    output.write_all(b"\"")?;
+    let mut rest = value.as_bytes();
+    while let Some(i) = rest
+        .iter()
+        .position(|&b| b < 0x20 || b == b'"' || b == b'\\')
+    {
+        output.write_all(&rest[..i])?;
+        match rest[i] {
+            b'"' => output.write_all(b"\\\"")?,
+            b'\\' => output.write_all(b"\\\\")?,
+            b'\n' => output.write_all(b"\\n")?,
+            b'\r' => output.write_all(b"\\r")?,
+            b'\t' => output.write_all(b"\\t")?,
+            byte => write!(output, "\\u{byte:04x}")?,
+        }
+        rest = &rest[i + 1..];
+    }
+    output.write_all(rest)?;
+    output.write_all(b"\"")?;
+    Ok(())
+}
+
+/// Converts a JSON array of arrays of strings or nulls to RSV.
+///
+/// Rows are written as soon as they are parsed. The `JsonMode` determines whether numbers and
+/// booleans are rejected or converted to strings. Nested arrays and objects are always rejected.
+pub fn from_json<R: BufRead, W: Write>(input: R, output: W, mode: JsonMode) -> Result<(), Error> {
+    let mut parser = Parser {
+        inner: input,
+        offset: 0,
+    };
+    let mut writer = RsvStreamWriter::new(output);
+
+    parser.expect(b'[')?;
+    if !parser.accept(b']')? {
+        loop {
+            parser.expect(b'[')?;
+            writer.start_row()?;
+            if !parser.accept(b']')? {
+                loop {
+                    writer.push(parser.value(mode)?.as_deref())?;
+                    if !parser.separator()? {
+                        break;
+                    }
+                }
+            }
+            if !parser.separator()? {
+                break;
+            }
+        }
+    }
+    match parser.peek_token()? {
+        None => {}
+        Some(_) => return Err(parser.error(JsonErrorKind::UnexpectedCharacter)),
+    }
+
+    writer.finish()?;
+    Ok(())
+}
+
+/// A minimal streaming parser for the subset of JSON used to represent RSV documents.
+struct Parser<R: BufRead> {
+    inner: R,
+    offset: usize,
+}
+
+impl<R: BufRead> Parser<R> {
+    fn error(&self, kind: JsonErrorKind) -> Error {
+        Error::Json {
+            offset: self.offset,
+            kind,
+        }
+    }
+
+    /// Returns the next byte without consuming it.
+    fn peek(&mut self) -> Result<Option<u8>, Error> {
+        Ok(self.inner.fill_buf()?.first().copied())
+    }
+
+    /// Consumes the next byte, which must have been peeked.
+    fn bump(&mut self) {
+        self.inner.consume(1);
+        self.offset += 1;
+    }
+
+    /// Consumes and returns the next byte, failing at the end of the input.
+    fn next(&mut self) -> Result<u8, Error> {
+        let byte = self
+            .peek()?
+            .ok_or_else(|| self.error(JsonErrorKind::UnexpectedEnd))?;
+        self.bump();
+        Ok(byte)
+    }
+
+    /// Skips whitespace, and returns the next byte without consuming it.
+    fn peek_token(&mut self) -> Result<Option<u8>, Error> {
+        while let Some(b' ' | b'\t' | b'\n' | b'\r') = self.peek()? {
+            self.bump();
+        }
+        self.peek()
+    }
+
+    /// Consumes the given byte if it is the next token.
+    fn accept(&mut self, byte: u8) -> Result<bool, Error> {
+        if self.peek_token()? == Some(byte) {
+            self.bump();
+            return Ok(true);
+        }
+        Ok(false)
+    }
+
+    /// Consumes the given byte, which must be the next token.
+    fn expect(&mut self, byte: u8) -> Result<(), Error> {
+        match self.peek_token()? {
+            Some(b) if b == byte => {
+                self.bump();
+                Ok(())
+            }
+            Some(_) => Err(self.error(JsonErrorKind::UnexpectedCharacter)),
+            None => Err(self.error(JsonErrorKind::UnexpectedEnd)),
+        }
+    }
+
+    /// Consumes either a `,`, returning `true`, or the `]` which closes the array, returning `false`.
+    fn separator(&mut self) -> Result<bool, Error> {
+        match self.peek_token()? {
+            Some(b',') => {
+                self.bump();
+                Ok(true)
+            }
+            Some(b']') => {
+                self.bump();
+                Ok(false)
+            }
+            Some(_) => Err(self.error(JsonErrorKind::UnexpectedCharacter)),
+            None => Err(self.error(JsonErrorKind::UnexpectedEnd)),
+        }
+    }
+
+    /// Parses a value within a row.
+    fn value(&mut self, mode: JsonMode) -> Result<Option<String>, Error> {
+        let start = self.offset;
+        let value = match self.peek_token()? {
+            Some(b'"') => return self.string().map(Some),
+            Some(b'n') => return self.literal("null").map(|_| None),
+            Some(b't') => self.literal("true")?,
+            Some(b'f') => self.literal("false")?,
+            Some(b'-' | b'0'..=b'9') => self.number()?,
+            Some(b'[' | b'{') => return Err(self.error(JsonErrorKind::NestedValue)),
+            Some(_) => return Err(self.error(JsonErrorKind::UnexpectedCharacter)),
+            None => return Err(self.error(JsonErrorKind::UnexpectedEnd)),
+        };
+        match mode {
+            JsonMode::Lenient => Ok(Some(value)),
+            JsonMode::Strict => Err(Error::Json {
+                offset: start,
+                kind: JsonErrorKind::NonStringValue,
+            }),
+        }
+    }
+
+    fn literal(&mut self, literal: &str) -> Result<String, Error> {
+        for &expected in literal.as_bytes() {
+            if self.next()? != expected {
+                return Err(Error::Json {
+                    offset: self.offset - 1,
+                    kind: JsonErrorKind::UnexpectedCharacter,
+                });
+            }
+        }
+        Ok(literal.to_string())
+    }
+
+    /// Parses a number, returning it exactly as written.
+    fn number(&mut self) -> Result<String, Error> {
+        let mut text = String::new();
+        let digits = |parser: &mut Self, text: &mut String| -> Result<usize, Error> {
+            let mut count = 0;
+            while let Some(b @ b'0'..=b'9') = parser.peek()? {
+                parser.bump();
+                text.push(b as char);
+                count += 1;
+            }
+            Ok(count)
+        };
+
+        if self.peek()? == Some(b'-') {
+            self.bump();
+            text.push('-');
+        }
+        let int_start = self.offset;
+        let int_digits = digits(self, &mut text)?;
+        // Leading zeros are not allowed
+        let leading_zero = int_digits > 1 && text.as_bytes()[text.len() - int_digits] == b'0';
+        if int_digits == 0 || leading_zero {
+            return Err(Error::Json {
+                offset: int_start,
+                kind: JsonErrorKind::InvalidNumber,
+            });
+        }
+        if self.peek()? == Some(b'.') {
+            self.bump();
+            text.push('.');
+            if digits(self, &mut text)? == 0 {
+                return Err(self.error(JsonErrorKind::InvalidNumber));
+            }
+        }
+        if let Some(e @ (b'e' | b'E')) = self.peek()? {
+            self.bump();
+            text.push(e as char);
+            if let Some(sign @ (b'+' | b'-')) = self.peek()? {
+                self.bump();
+                text.push(sign as char);
+            }
+            if digits(self, &mut text)? == 0 {
+                return Err(self.error(JsonErrorKind::InvalidNumber));
+            }
+        }
+        Ok(text)
+    }
+
+    /// Parses a string literal, decoding any escape sequences.
+    fn string(&mut self) -> Result<String, Error> {
+        let start = self.offset + 1;
+        self.bump();
+        let mut bytes = Vec::new();
+        loop {
+            // Copy everything up to the next quote, backslash or control character in one go
+            let buffer = self.inner.fill_buf()?;
+            let len = buffer
+                .iter()
+                .position(|&b| b == b'"' || b == b'\\' || b < 0x20)
+                .unwrap_or(buffer.len());
+            bytes.extend_from_slice(&buffer[..len]);
+            self.inner.consume(len);
+            self.offset += len;
+
+            match self.next()? {
+                b'"' => break,
+                b'\\' => {
+                    let escaped = match self.next()? {
+                        b'"' => '"',
+                        b'\\' => '\\',
+                        b'/' => '/',
+                        b'b' => '\x08',
+                        b'f' => '\x0C',
+                        b'n' => '\n',
+                        b'r' => '\r',
+                        b't' => '\t',
+                        b'u' => self.unicode_escape()?,
+                        _ => return Err(self.error(JsonErrorKind::InvalidString)),
+                    };
+                    let mut buf = [0; 4];
+                    bytes.extend_from_slice(escaped.encode_utf8(&mut buf).as_bytes());
+                }
+                _ => {
+                    self.offset -= 1;
+                    return Err(self.error(JsonErrorKind::InvalidString));
+                }
+            }
+        }
+        String::from_utf8(bytes).map_err(|err| Error::Json {
+            offset: start + err.utf8_error().valid_up_to(),
+            kind: JsonErrorKind::BadUTF8,
+        })
+    }
+
+    /// Parses the hex digits of a `\u` escape, including the second half of a surrogate pair.
+    fn unicode_escape(&mut self) -> Result<char, Error> {
+        let high = self.hex4()?;
+        let code = match high {
+            0xD800..=0xDBFF => {
+                if self.next()? != b'\\' || self.next()? != b'u' {
+                    return Err(self.error(JsonErrorKind::InvalidString));
+                }
+                let low = self.hex4()?;
+                if !(0xDC00..=0xDFFF).contains(&low) {
+                    return Err(self.error(JsonErrorKind::InvalidString));
+                }
+                0x10000 + ((high - 0xD800) << 10) + (low - 0xDC00)
+            }
+            code => code,
+        };
+        std::char::from_u32(code).ok_or_else(|| self.error(JsonErrorKind::InvalidString))
+    }
+
+    fn hex4(&mut self) -> Result<u32, Error> {
+        let mut code = 0;
+        for _ in 0..4 {
+            let digit = (self.next()? as char)
+                .to_digit(16)
+                .ok_or_else(|| self.error(JsonErrorKind::InvalidString))?;
+            code = code * 16 + digit;
+        }
+        Ok(code)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{decode_rsv, encode_rsv};
+
+    fn parse(json: &str, mode: JsonMode) -> Result<Vec<Vec<Option<String>>>, Error> {
+        let mut rsv = vec![];
+        from_json(json.as_bytes(), &mut rsv, mode)?;
+        Ok(decode_rsv(&rsv).unwrap())
+    }
+
+    fn s(value: &str) -> Option<String> {
+        Some(value.to_string())
+    }
+
+    #[test]
+    fn roundtrip() {
+        let data = vec![
+            vec![Some("Hello"), Some("wörld 🌍"), None],
+            vec![],
+            vec![Some(""), Some("\"quoted\"\n\ttabbed\\ \u{1} \u{7f}")],
+        ];
+        let rsv = encode_rsv(&data);
+        let mut json = vec![];
+        to_json(&rsv[..], &mut json).unwrap();
+        let mut output = vec![];
+        from_json(&json[..], &mut output, JsonMode::Strict).unwrap();
+        assert_eq!(output, rsv);
+    }
+
+    #[test]
+    fn empty_document() {
+        let mut json = vec![];
+        to_json(&b""[..], &mut json).unwrap();
+        assert_eq!(json, b"[]");
+        assert!(parse(" [ ] ", JsonMode::Strict).unwrap().is_empty());
+    }
+
+    #[test]
+    fn decodes_escapes() {
+        let json = r#"[["é🌍\/\b\f"]]"#;
+        assert_eq!(
+            parse(json, JsonMode::Strict).unwrap(),
+            vec![vec![s("é🌍/\x08\x0C")]]
+        );
+        assert!(parse(r#"[["\ud83c"]]"#, JsonMode::Strict).is_err());
+        assert!(parse(r#"[["\x"]]"#, JsonMode::Strict).is_err());
+        assert!(parse("[[\"\n\"]]", JsonMode::Strict).is_err());
+    }
+
+    #[test]
+    fn scalar_modes() {
+        let json = "[[1, -2.50e+3, true, false, null]]";
+        assert!(matches!(
+            parse(json, JsonMode::Strict),
+            Err(Error::Json {
+                offset: 2,
+                kind: JsonErrorKind::NonStringValue
+            })
+        ));
+        assert_eq!(
+            parse(json, JsonMode::Lenient).unwrap(),
+            vec![vec![s("1"), s("-2.50e+3"), s("true"), s("false"), None]]
+        );
+        for bad in ["[[01]]", "[[-]]", "[[1.]]", "[[1e]]", "[[tru]]"] {
+            assert!(parse(bad, JsonMode::Lenient).is_err(), "{}", bad);
+        }
+    }
+
+    #[test]
+    fn rejects_bad_structure() {
+        let kind = |json: &str| match parse(json, JsonMode::Lenient) {
+            Err(Error::Json { kind, .. }) => kind,
+            other => panic!("expected an error, got {:?}", other),
+        };
+        assert_eq!(kind("[[[\"a\"]]]"), JsonErrorKind::NestedValue);
+        assert_eq!(kind("[[{}]]"), JsonErrorKind::NestedValue);
+        assert_eq!(kind("[\"a\"]"), JsonErrorKind::UnexpectedCharacter);
+        assert_eq!(kind("[[\"a\"]"), JsonErrorKind::UnexpectedEnd);
+        assert_eq!(kind("[[\"a\",]]"), JsonErrorKind::UnexpectedCharacter);
+        assert_eq!(kind("[] []"), JsonErrorKind::UnexpectedCharacter);
+    }
+}
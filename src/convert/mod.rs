@@ -0,0 +1,44 @@
+//! Conversion between RSV and other tabular formats.
+
+use std::io;
+use thiserror::Error;
+
+pub mod csv;
+pub mod json;
+
+/// An error encountered while converting between RSV and another format.
+#[derive(Error, Debug)]
+pub enum Error {
+    /// The RSV input was invalid.
+    #[error(transparent)]
+    Rsv(#[from] crate::Error),
+    /// An I/O error occurred while reading or writing.
+    #[error("I/O error: {0}")]
+    Io(#[from] io::Error),
+    /// The CSV input was invalid.
+    #[error("invalid CSV on line {line} (byte {offset}): {kind}")]
+    Csv {
+        /// The byte offset in the input at which the problem was found.
+        offset: usize,
+        /// The one-based line number on which the problem was found.
+        line: usize,
+        /// What was wrong with the input.
+        kind: csv::CsvErrorKind,
+    },
+    /// The JSON input was invalid.
+    #[error("invalid JSON at byte {offset}: {kind}")]
+    Json {
+        /// The byte offset in the input at which the problem was found.
+        offset: usize,
+        /// What was wrong with the input.
+        kind: json::JsonErrorKind,
+    },
+    /// A null value was encountered, but the output format has no way to represent it.
+    #[error("row {row}, value {value} is null, which is not allowed by the output format")]
+    UnexpectedNull {
+        /// The index of the row containing the null value.
+        row: usize,
+        /// The index of the null value within its row.
+        value: usize,
+    },
+}
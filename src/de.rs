@@ -0,0 +1,495 @@
+use crate::{Error, RsvReader, RsvRow, RsvStreamReader};
+use serde::de::value::{BorrowedStrDeserializer, StringDeserializer};
+use serde::de::{self, DeserializeOwned, DeserializeSeed, Visitor};
+use serde::{forward_to_deserialize_any, Deserialize};
+use std::borrow::Cow;
+use std::io::{BufReader, Read};
+use std::str::FromStr;
+
+/// Deserializes a sequence of rows from an RSV document.
+///
+/// Each row is deserialized as a struct, tuple or sequence, whose fields are read from the values in order.
+/// Numbers, bools and chars are parsed from their string form, and null values can be read into an `Option`.
+/// String values can be borrowed from the input.
+///
+/// # Example:
+/// ```
+/// #[derive(serde::Deserialize, PartialEq, Debug)]
+/// struct Person<'a> {
+///     name: &'a str,
+///     age: Option<u32>,
+/// }
+///
+/// let buffer = b"Alice\xFF30\xFF\xFDBob\xFF\xFE\xFF\xFD";
+/// let people: Vec<Person> = librsv::from_slice(buffer).unwrap();
+///
+/// assert_eq!(people, vec![
+///     Person { name: "Alice", age: Some(30) },
+///     Person { name: "Bob", age: None },
+/// ]);
+/// ```
+pub fn from_slice<'a, T>(data: &'a [u8]) -> Result<T, Error>
+where
+    T: Deserialize<'a>,
+{
+    let reader = RsvReader::new(data);
+    let mut rows = SliceRows(reader.rows());
+    let value = T::deserialize(DocumentDeserializer { rows: &mut rows })?;
+    rows.end()?;
+    Ok(value)
+}
+
+/// Deserializes a sequence of rows from an RSV document read from the given source.
+///
+/// Rows are read incrementally, so the encoded document is never held in memory.
+/// See `from_slice` for details on how values are decoded.
+pub fn from_reader<R, T>(reader: R) -> Result<T, Error>
+where
+    R: Read,
+    T: DeserializeOwned,
+{
+    let mut rows = StreamRows(RsvStreamReader::new(BufReader::new(reader)));
+    let value = T::deserialize(DocumentDeserializer { rows: &mut rows })?;
+    rows.end()?;
+    Ok(value)
+}
+
+impl de::Error for Error {
+    fn custom<T: std::fmt::Display>(msg: T) -> Self {
+        Error::Message(msg.to_string())
+    }
+}
+
+/// A decoded row, ready to be deserialized.
+struct Row<'de> {
+    index: usize,
+    values: Vec<Option<Cow<'de, str>>>,
+}
+
+/// A source of rows to be deserialized.
+trait RowSource<'de> {
+    fn next_row(&mut self) -> Result<Option<Row<'de>>, Error>;
+
+    /// Checks that there are no rows left over after deserializing.
+    fn end(&mut self) -> Result<(), Error> {
+        match self.next_row()? {
+            Some(row) => Err(Error::Message(format!(
+                "unexpected trailing row {}",
+                row.index
+            ))),
+            None => Ok(()),
+        }
+    }
+}
+
+/// Rows borrowed from an in-memory buffer.
+struct SliceRows<I>(I);
+
+impl<'de, I> RowSource<'de> for SliceRows<I>
+where
+    I: Iterator<Item = Result<RsvRow<'de>, Error>>,
+{
+    fn next_row(&mut self) -> Result<Option<Row<'de>>, Error> {
+        let Some(row) = self.0.next().transpose()? else {
+            return Ok(None);
+        };
+        let values = row
+            .values()
+            .map(|v| v.map(|v| v.map(Cow::Borrowed)))
+            .collect::<Result<_, _>>()?;
+        Ok(Some(Row {
+            index: row.index(),
+            values,
+        }))
+    }
+}
+
+/// Rows read from a stream.
+struct StreamRows<R: std::io::BufRead>(RsvStreamReader<R>);
+
+impl<'de, R: std::io::BufRead> RowSource<'de> for StreamRows<R> {
+    fn next_row(&mut self) -> Result<Option<Row<'de>>, Error> {
+        let Some(row) = self.0.next_row()? else {
+            return Ok(None);
+        };
+        let values = row
+            .values()
+            .map(|v| v.map(|v| v.map(|v| Cow::Owned(v.to_string()))))
+            .collect::<Result<_, _>>()?;
+        Ok(Some(Row {
+            index: row.index(),
+            values,
+        }))
+    }
+}
+
+/// Deserializes the document as a sequence of rows.
+struct DocumentDeserializer<'a, S> {
+    rows: &'a mut S,
+}
+
+impl<'de, 'a, S: RowSource<'de>> de::Deserializer<'de> for DocumentDeserializer<'a, S> {
+    type Error = Error;
+
+    fn deserialize_any<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, Error> {
+        visitor.visit_seq(self)
+    }
+
+    fn deserialize_newtype_struct<V: Visitor<'de>>(
+        self,
+        _: &'static str,
+        visitor: V,
+    ) -> Result<V::Value, Error> {
+        visitor.visit_newtype_struct(self)
+    }
+
+    forward_to_deserialize_any! {
+        bool i8 i16 i32 i64 i128 u8 u16 u32 u64 u128 f32 f64 char str string
+        bytes byte_buf option unit unit_struct seq tuple
+        tuple_struct map struct enum identifier ignored_any
+    }
+}
+
+impl<'de, 'a, S: RowSource<'de>> de::SeqAccess<'de> for DocumentDeserializer<'a, S> {
+    type Error = Error;
+
+    fn next_element_seed<T: DeserializeSeed<'de>>(
+        &mut self,
+        seed: T,
+    ) -> Result<Option<T::Value>, Error> {
+        let Some(row) = self.rows.next_row()? else {
+            return Ok(None);
+        };
+        let mut row = RowDeserializer {
+            index: row.index,
+            values: row.values.into_iter().enumerate(),
+        };
+        let value = seed.deserialize(&mut row)?;
+        if row.values.len() > 0 {
+            return Err(Error::Message(format!(
+                "row {} has {} more values than expected",
+                row.index,
+                row.values.len()
+            )));
+        }
+        Ok(Some(value))
+    }
+}
+
+/// Deserializes a single row as a sequence of values.
+struct RowDeserializer<'de> {
+    index: usize,
+    values: std::iter::Enumerate<std::vec::IntoIter<Option<Cow<'de, str>>>>,
+}
+
+impl<'de> de::Deserializer<'de> for &mut RowDeserializer<'de> {
+    type Error = Error;
+
+    fn deserialize_any<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, Error> {
+        visitor.visit_seq(self)
+    }
+
+    fn deserialize_newtype_struct<V: Visitor<'de>>(
+        self,
+        _: &'static str,
+        visitor: V,
+    ) -> Result<V::Value, Error> {
+        visitor.visit_newtype_struct(self)
+    }
+
+    forward_to_deserialize_any! {
+        bool i8 i16 i32 i64 i128 u8 u16 u32 u64 u128 f32 f64 char str string
+        bytes byte_buf option unit unit_struct seq tuple
+        tuple_struct map struct enum identifier ignored_any
+    }
+}
+
+impl<'de> de::SeqAccess<'de> for &mut RowDeserializer<'de> {
+    type Error = Error;
+
+    fn next_element_seed<T: DeserializeSeed<'de>>(
+        &mut self,
+        seed: T,
+    ) -> Result<Option<T::Value>, Error> {
+        let Some((index, value)) = self.values.next() else {
+            return Ok(None);
+        };
+        seed.deserialize(ValueDeserializer {
+            value,
+            row: self.index,
+            index,
+        })
+        .map(Some)
+    }
+
+    fn size_hint(&self) -> Option<usize> {
+        Some(self.values.len())
+    }
+}
+
+/// Deserializes a single value.
+struct ValueDeserializer<'de> {
+    value: Option<Cow<'de, str>>,
+    row: usize,
+    index: usize,
+}
+
+impl<'de> ValueDeserializer<'de> {
+    fn error(&self, msg: impl std::fmt::Display) -> Error {
+        Error::Message(format!("row {}, value {}: {}", self.row, self.index, msg))
+    }
+
+    /// Returns the string value, or an error if the value is null.
+    fn into_str(self, expected: &str) -> Result<Cow<'de, str>, Error> {
+        match self.value {
+            Some(value) => Ok(value),
+            None => Err(self.error(format_args!("expected {expected}, found null"))),
+        }
+    }
+
+    /// Parses the value from its string form.
+    fn parse<T>(&self, expected: &str) -> Result<T, Error>
+    where
+        T: FromStr,
+        T::Err: std::fmt::Display,
+    {
+        let Some(value) = &self.value else {
+            return Err(self.error(format_args!("expected {expected}, found null")));
+        };
+        value
+            .parse()
+            .map_err(|err| self.error(format_args!("cannot parse {value:?} as {expected}: {err}")))
+    }
+}
+
+/// Implements the deserializer methods for types which are parsed from their string form.
+macro_rules! deserialize_parsed {
+    ($($method:ident => $visit:ident($ty:ty),)*) => {
+        $(
+            fn $method<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, Error> {
+                visitor.$visit(self.parse::<$ty>(stringify!($ty))?)
+            }
+        )*
+    };
+}
+
+impl<'de> de::Deserializer<'de> for ValueDeserializer<'de> {
+    type Error = Error;
+
+    fn deserialize_any<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, Error> {
+        match self.value {
+            Some(Cow::Borrowed(value)) => visitor.visit_borrowed_str(value),
+            Some(Cow::Owned(value)) => visitor.visit_string(value),
+            None => visitor.visit_none(),
+        }
+    }
+
+    deserialize_parsed! {
+        deserialize_bool => visit_bool(bool),
+        deserialize_i8 => visit_i8(i8),
+        deserialize_i16 => visit_i16(i16),
+        deserialize_i32 => visit_i32(i32),
+        deserialize_i64 => visit_i64(i64),
+        deserialize_i128 => visit_i128(i128),
+        deserialize_u8 => visit_u8(u8),
+        deserialize_u16 => visit_u16(u16),
+        deserialize_u32 => visit_u32(u32),
+        deserialize_u64 => visit_u64(u64),
+        deserialize_u128 => visit_u128(u128),
+        deserialize_f32 => visit_f32(f32),
+        deserialize_f64 => visit_f64(f64),
+        deserialize_char => visit_char(char),
+    }
+
+    fn deserialize_str<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, Error> {
+        match self.into_str("a string")? {
+            Cow::Borrowed(value) => visitor.visit_borrowed_str(value),
+            Cow::Owned(value) => visitor.visit_string(value),
+        }
+    }
+
+    fn deserialize_string<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, Error> {
+        self.deserialize_str(visitor)
+    }
+
+    fn deserialize_bytes<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, Error> {
+        match self.into_str("bytes")? {
+            Cow::Borrowed(value) => visitor.visit_borrowed_bytes(value.as_bytes()),
+            Cow::Owned(value) => visitor.visit_byte_buf(value.into_bytes()),
+        }
+    }
+
+    fn deserialize_byte_buf<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, Error> {
+        self.deserialize_bytes(visitor)
+    }
+
+    fn deserialize_option<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, Error> {
+        match self.value {
+            Some(_) => visitor.visit_some(self),
+            None => visitor.visit_none(),
+        }
+    }
+
+    fn deserialize_unit<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, Error> {
+        match self.value {
+            Some(_) => Err(self.error("expected null")),
+            None => visitor.visit_unit(),
+        }
+    }
+
+    fn deserialize_unit_struct<V: Visitor<'de>>(
+        self,
+        _: &'static str,
+        visitor: V,
+    ) -> Result<V::Value, Error> {
+        self.deserialize_unit(visitor)
+    }
+
+    fn deserialize_newtype_struct<V: Visitor<'de>>(
+        self,
+        _: &'static str,
+        visitor: V,
+    ) -> Result<V::Value, Error> {
+        visitor.visit_newtype_struct(self)
+    }
+
+    fn deserialize_seq<V: Visitor<'de>>(self, _: V) -> Result<V::Value, Error> {
+        Err(self.error("cannot deserialize a nested sequence from an RSV value"))
+    }
+
+    fn deserialize_tuple<V: Visitor<'de>>(self, _: usize, _: V) -> Result<V::Value, Error> {
+        Err(self.error("cannot deserialize a nested tuple from an RSV value"))
+    }
+
+    fn deserialize_tuple_struct<V: Visitor<'de>>(
+        self,
+        _: &'static str,
+        _: usize,
+        _: V,
+    ) -> Result<V::Value, Error> {
+        Err(self.error("cannot deserialize a nested tuple struct from an RSV value"))
+    }
+
+    fn deserialize_map<V: Visitor<'de>>(self, _: V) -> Result<V::Value, Error> {
+        Err(self.error("cannot deserialize a nested map from an RSV value"))
+    }
+
+    fn deserialize_struct<V: Visitor<'de>>(
+        self,
+        _: &'static str,
+        _: &'static [&'static str],
+        _: V,
+    ) -> Result<V::Value, Error> {
+        Err(self.error("cannot deserialize a nested struct from an RSV value"))
+    }
+
+    fn deserialize_enum<V: Visitor<'de>>(
+        self,
+        _: &'static str,
+        _: &'static [&'static str],
+        visitor: V,
+    ) -> Result<V::Value, Error> {
+        match self.into_str("an enum variant")? {
+            Cow::Borrowed(value) => visitor.visit_enum(BorrowedStrDeserializer::new(value)),
+            Cow::Owned(value) => visitor.visit_enum(StringDeserializer::new(value)),
+        }
+    }
+
+    fn deserialize_identifier<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, Error> {
+        self.deserialize_str(visitor)
+    }
+
+    fn deserialize_ignored_any<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, Error> {
+        visitor.visit_unit()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{encode_rsv, to_vec};
+    use serde::Serialize;
+
+    #[derive(Serialize, Deserialize, PartialEq, Debug)]
+    enum Kind {
+        Cat,
+        Dog,
+    }
+
+    #[derive(Serialize, Deserialize, PartialEq, Debug)]
+    struct Pet {
+        name: String,
+        kind: Kind,
+        age: Option<u8>,
+        weight: f64,
+        vaccinated: bool,
+        initial: char,
+    }
+
+    #[test]
+    fn roundtrip_structs() {
+        let pets = vec![
+            Pet {
+                name: "Rex".into(),
+                kind: Kind::Dog,
+                age: Some(3),
+                weight: 12.5,
+                vaccinated: true,
+                initial: 'R',
+            },
+            Pet {
+                name: "".into(),
+                kind: Kind::Cat,
+                age: None,
+                weight: -0.25,
+                vaccinated: false,
+                initial: 'é',
+            },
+        ];
+        let buffer = to_vec(&pets).unwrap();
+        assert_eq!(from_slice::<Vec<Pet>>(&buffer).unwrap(), pets);
+        assert_eq!(from_reader::<_, Vec<Pet>>(&buffer[..]).unwrap(), pets);
+    }
+
+    #[test]
+    fn roundtrip_tuples() {
+        let rows = vec![(1u32, Some("a".to_string())), (2, None)];
+        let buffer = to_vec(&rows).unwrap();
+        assert_eq!(
+            buffer,
+            encode_rsv([[Some("1"), Some("a")], [Some("2"), None]])
+        );
+        assert_eq!(
+            from_slice::<Vec<(u32, Option<String>)>>(&buffer).unwrap(),
+            rows
+        );
+    }
+
+    #[test]
+    fn borrows_strings() {
+        let buffer = b"Hello\xFFworld\xFF\xFD";
+        let rows: Vec<Vec<&str>> = from_slice(buffer).unwrap();
+        assert_eq!(rows, vec![vec!["Hello", "world"]]);
+    }
+
+    #[test]
+    fn reports_bad_values() {
+        let buffer = encode_rsv([[Some("1")], [Some("x")]]);
+        let err = from_slice::<Vec<(u8,)>>(&buffer).unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "row 1, value 0: cannot parse \"x\" as u8: invalid digit found in string"
+        );
+
+        let buffer = encode_rsv([[None::<&str>]]);
+        let err = from_slice::<Vec<(u8,)>>(&buffer).unwrap_err();
+        assert_eq!(err.to_string(), "row 0, value 0: expected u8, found null");
+    }
+
+    #[test]
+    fn rejects_mismatched_row_widths() {
+        let buffer = encode_rsv([[Some("1"), Some("2")]]);
+        assert!(from_slice::<Vec<(u8,)>>(&buffer).is_err());
+        assert!(from_slice::<Vec<(u8, u8, u8)>>(&buffer).is_err());
+    }
+}
@@ -0,0 +1,222 @@
+use core::fmt::{self, Display, Write};
+use core::marker::PhantomData;
+use core::slice;
+
+/// A type which can be written as a single RSV value, for use with `encode_rsv` and `write_row`.
+///
+/// This is implemented for any type implementing `AsRef<str>`, such as `&str`, `String`,
+/// `Cow<str>`, `Box<str>` or `Arc<str>`, and for the primitive types, all of which are never
+/// null. It is also implemented for `Option<T>`, which writes `None` as null, and for references
+/// to these types, so that borrowed documents can be encoded.
+///
+/// Values are written with their `Display` implementation, straight into the output, so that
+/// encoding numbers does not allocate. The type parameter only tells the implementations for
+/// strings, primitives and options apart, and is always inferred.
+pub trait IntoRsvValue<M> {
+    /// The type the value is written as.
+    type Value: Display;
+
+    /// Converts the value to one which can be written, or `None` if it is null.
+    fn into_rsv_value(self) -> Option<Self::Value>;
+}
+
+/// Marks the implementation of `IntoRsvValue` for `AsRef<str>` types.
+pub enum Text {}
+
+/// Marks the implementations of `IntoRsvValue` for primitive types.
+pub enum Primitive {}
+
+/// Marks the implementation of `IntoRsvValue` for `Option<T>`.
+pub struct Nullable<M>(PhantomData<M>);
+
+/// Marks the implementation of `IntoRsvValue` for `&Option<T>`.
+pub struct RefNullable<M>(PhantomData<M>);
+
+/// Displays a string-like value as it is.
+pub struct Str<T>(T);
+
+impl<T: AsRef<str>> Display for Str<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.0.as_ref())
+    }
+}
+
+impl<T: AsRef<str>> IntoRsvValue<Text> for T {
+    type Value = Str<T>;
+
+    fn into_rsv_value(self) -> Option<Self::Value> {
+        Some(Str(self))
+    }
+}
+
+impl<T: IntoRsvValue<M>, M> IntoRsvValue<Nullable<M>> for Option<T> {
+    type Value = T::Value;
+
+    fn into_rsv_value(self) -> Option<Self::Value> {
+        self.and_then(T::into_rsv_value)
+    }
+}
+
+impl<'a, T, M> IntoRsvValue<RefNullable<M>> for &'a Option<T>
+where
+    &'a T: IntoRsvValue<M>,
+{
+    type Value = <&'a T as IntoRsvValue<M>>::Value;
+
+    fn into_rsv_value(self) -> Option<Self::Value> {
+        self.as_ref().and_then(IntoRsvValue::into_rsv_value)
+    }
+}
+
+macro_rules! primitive_values {
+    ($($ty:ty),*) => {
+        $(
+            impl IntoRsvValue<Primitive> for $ty {
+                type Value = $ty;
+
+                fn into_rsv_value(self) -> Option<Self::Value> {
+                    Some(self)
+                }
+            }
+
+            impl IntoRsvValue<Primitive> for &$ty {
+                type Value = $ty;
+
+                fn into_rsv_value(self) -> Option<Self::Value> {
+                    Some(*self)
+                }
+            }
+        )*
+    };
+}
+
+primitive_values!(
+    i8, i16, i32, i64, i128, isize, u8, u16, u32, u64, u128, usize, f32, f64, bool, char
+);
+
+/// A type which can be written as a whole RSV row, for use with `encode_rsv` and `write_row`.
+///
+/// This is implemented for any iterator or collection of values implementing `IntoRsvValue`, and
+/// for references to slice references, such as the rows of a `&[&[Option<&str>]]`, which are not
+/// iterable themselves. The type parameter only tells these implementations apart, and is always
+/// inferred.
+pub trait IntoRsvRow<M> {
+    /// The type of each value in the row.
+    type Value: IntoRsvValue<Self::Marker>;
+    /// The type parameter of the values' `IntoRsvValue` implementation.
+    type Marker;
+    /// The iterator over the values in the row.
+    type IntoIter: Iterator<Item = Self::Value>;
+
+    /// Converts the row to an iterator over its values.
+    fn into_rsv_row(self) -> Self::IntoIter;
+}
+
+/// Marks the implementation of `IntoRsvRow` for iterators and collections.
+pub struct Values<M>(PhantomData<M>);
+
+/// Marks the implementation of `IntoRsvRow` for references to slice references.
+pub struct SliceRef<M>(PhantomData<M>);
+
+impl<I, M> IntoRsvRow<Values<M>> for I
+where
+    I: IntoIterator,
+    I::Item: IntoRsvValue<M>,
+{
+    type Value = I::Item;
+    type Marker = M;
+    type IntoIter = I::IntoIter;
+
+    fn into_rsv_row(self) -> Self::IntoIter {
+        self.into_iter()
+    }
+}
+
+impl<'a, V, M> IntoRsvRow<SliceRef<M>> for &&'a [V]
+where
+    &'a V: IntoRsvValue<M>,
+{
+    type Value = &'a V;
+    type Marker = M;
+    type IntoIter = slice::Iter<'a, V>;
+
+    fn into_rsv_row(self) -> Self::IntoIter {
+        self.iter()
+    }
+}
+
+/// Formats a value, passing its encoded bytes to `write` as they are produced.
+///
+/// The output is valid UTF-8, so cannot contain any of the reserved bytes.
+pub(crate) fn format_value(value: impl Display, write: impl FnMut(&[u8])) {
+    struct Adapter<F>(F);
+
+    impl<F: FnMut(&[u8])> Write for Adapter<F> {
+        fn write_str(&mut self, s: &str) -> fmt::Result {
+            (self.0)(s.as_bytes());
+            Ok(())
+        }
+    }
+
+    write!(Adapter(write), "{}", value)
+        .expect("a Display implementation returned an error unexpectedly");
+}
+
+#[cfg(all(test, feature = "alloc"))]
+mod tests {
+    use super::*;
+    use alloc::borrow::Cow;
+    use alloc::boxed::Box;
+    use alloc::rc::Rc;
+    use alloc::string::{String, ToString};
+    use alloc::vec::Vec;
+
+    fn encode<M>(value: impl IntoRsvValue<M>) -> Option<String> {
+        value.into_rsv_value().map(|value| value.to_string())
+    }
+
+    #[test]
+    fn into_values() {
+        let name = String::from("b");
+        let row = [
+            encode(Some("a")),
+            encode(&name),
+            encode(Some(Cow::Borrowed("c"))),
+            encode(None::<String>),
+            encode(-1.5),
+            encode(Some(&42u8)),
+            encode(true),
+            encode(Box::<str>::from("d")),
+            encode(Some(Rc::<str>::from("e"))),
+        ];
+        assert_eq!(
+            row.iter().map(Option::as_deref).collect::<Vec<_>>(),
+            [
+                Some("a"),
+                Some("b"),
+                Some("c"),
+                None,
+                Some("-1.5"),
+                Some("42"),
+                Some("true"),
+                Some("d"),
+                Some("e")
+            ]
+        );
+
+        let chars = [Some('x'), None];
+        assert_eq!(
+            chars.iter().map(encode).collect::<Vec<_>>(),
+            [Some("x".into()), None]
+        );
+        assert_eq!(
+            ["y"].iter().map(encode).collect::<Vec<_>>(),
+            [Some("y".into())]
+        );
+
+        let mut bytes = Vec::new();
+        format_value(1234, |b| bytes.extend_from_slice(b));
+        format_value(Str(&name), |b| bytes.extend_from_slice(b));
+        assert_eq!(bytes, b"1234b");
+    }
+}
@@ -0,0 +1,237 @@
+use crate::{rows_at, Error, RsvReader, RsvRow};
+use alloc::collections::BTreeMap;
+use alloc::string::ToString;
+use alloc::vec::Vec;
+
+/// The column names read from the header row of an RSV document.
+#[derive(Clone, Debug, Default)]
+pub struct RsvHeaders<'a> {
+    names: Vec<&'a str>,
+    lookup: BTreeMap<&'a str, usize>,
+}
+
+impl<'a> RsvHeaders<'a> {
+    /// Reads the column names from a header row.
+    ///
+    /// Every column must have a unique, non-null name.
+    pub fn from_row(row: &RsvRow<'a>) -> Result<Self, Error> {
+        let mut headers = Self::default();
+        for (column, name) in row.values().enumerate() {
+            let Some(name) = name? else {
+                return Err(Error::NullHeader {
+                    offset: row.offset(),
+                    column,
+                });
+            };
+            if let Some(&first) = headers.lookup.get(name) {
+                return Err(Error::DuplicateHeader {
+                    name: name.to_string(),
+                    first,
+                    second: column,
+                });
+            }
+            headers.lookup.insert(name, column);
+            headers.names.push(name);
+        }
+        Ok(headers)
+    }
+
+    /// The number of columns.
+    pub fn len(&self) -> usize {
+        self.names.len()
+    }
+
+    /// Whether there are no columns.
+    pub fn is_empty(&self) -> bool {
+        self.names.is_empty()
+    }
+
+    /// Returns the name of the column at the given index.
+    pub fn get(&self, index: usize) -> Option<&'a str> {
+        self.names.get(index).copied()
+    }
+
+    /// Returns the index of the column with the given name.
+    pub fn index_of(&self, name: &str) -> Option<usize> {
+        self.lookup.get(name).copied()
+    }
+
+    /// Iterates over the column names in order.
+    pub fn iter(&self) -> impl Iterator<Item = &'a str> + '_ {
+        self.names.iter().copied()
+    }
+}
+
+/// Reads an RSV document whose first row contains the column names.
+///
+/// # Example:
+/// ```
+/// use librsv::RsvReader;
+///
+/// let buffer = b"name\xFFage\xFF\xFDAlice\xFF30\xFF\xFDBob\xFF\xFE\xFF\xFD";
+/// let reader = RsvReader::new(buffer).with_headers()?;
+///
+/// for row in reader.rows() {
+///     let row = row?;
+///     println!("{:?} is {:?} years old", row.get("name"), row.get("age"));
+/// }
+/// # Ok::<(), librsv::Error>(())
+/// ```
+pub struct RsvHeaderReader<'a> {
+    headers: RsvHeaders<'a>,
+    data: &'a [u8],
+    offset: usize,
+}
+
+impl<'a> RsvReader<'a> {
+    /// Reads the first row of the document as a header row containing the column names.
+    ///
+    /// An empty document is treated as having no columns and no rows.
+    pub fn with_headers(&self) -> Result<RsvHeaderReader<'a>, Error> {
+        let Some(row) = self.rows().next().transpose()? else {
+            return Ok(RsvHeaderReader {
+                headers: RsvHeaders::default(),
+                data: &[],
+                offset: 0,
+            });
+        };
+        let offset = row.data.len() + 1;
+        Ok(RsvHeaderReader {
+            headers: RsvHeaders::from_row(&row)?,
+            data: &self.data[offset..],
+            offset,
+        })
+    }
+}
+
+impl<'a> RsvHeaderReader<'a> {
+    /// The column names from the header row.
+    pub fn headers(&self) -> &RsvHeaders<'a> {
+        &self.headers
+    }
+
+    /// Iterates over the rows following the header row.
+    ///
+    /// Each row must have exactly as many values as there are columns.
+    pub fn rows(&self) -> impl Iterator<Item = Result<RsvHeaderRow<'_, 'a>, Error>> {
+        rows_at(self.data, self.offset, 1).map(move |row| {
+            let row = row?;
+            let values = row.values().collect::<Result<Vec<_>, _>>()?;
+            if values.len() != self.headers.len() {
+                return Err(Error::RowWidthMismatch {
+                    offset: row.offset(),
+                    row: row.index(),
+                    expected: self.headers.len(),
+                    found: values.len(),
+                });
+            }
+            Ok(RsvHeaderRow {
+                headers: &self.headers,
+                index: row.index(),
+                values,
+            })
+        })
+    }
+}
+
+/// A row of an RSV document whose values can be looked up by column name.
+pub struct RsvHeaderRow<'h, 'a> {
+    headers: &'h RsvHeaders<'a>,
+    index: usize,
+    values: Vec<Option<&'a str>>,
+}
+
+impl<'h, 'a> RsvHeaderRow<'h, 'a> {
+    /// The zero-based index of this row within the document, including the header row.
+    pub fn index(&self) -> usize {
+        self.index
+    }
+
+    /// The column names from the header row.
+    pub fn headers(&self) -> &'h RsvHeaders<'a> {
+        self.headers
+    }
+
+    /// Returns the value in the column with the given name, or `None` if there is no such column.
+    pub fn get(&self, name: &str) -> Option<Option<&'a str>> {
+        self.get_by_index(self.headers.index_of(name)?)
+    }
+
+    /// Returns the value in the column at the given index, or `None` if there is no such column.
+    pub fn get_by_index(&self, index: usize) -> Option<Option<&'a str>> {
+        self.values.get(index).copied()
+    }
+
+    /// The values in this row, in column order.
+    pub fn values(&self) -> &[Option<&'a str>] {
+        &self.values
+    }
+
+    /// Iterates over the column names and values in this row.
+    pub fn iter(&self) -> impl Iterator<Item = (&'a str, Option<&'a str>)> + '_ {
+        self.headers.iter().zip(self.values.iter().copied())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::encode_rsv;
+    use alloc::vec;
+
+    #[test]
+    fn lookup_by_name() {
+        let buffer = encode_rsv([
+            [Some("name"), Some("age")],
+            [Some("Alice"), Some("30")],
+            [Some("Bob"), None],
+        ]);
+        let reader = RsvReader::new(&buffer).with_headers().unwrap();
+        assert_eq!(reader.headers().iter().collect::<Vec<_>>(), ["name", "age"]);
+
+        let rows = reader.rows().collect::<Result<Vec<_>, _>>().unwrap();
+        assert_eq!(rows[0].get("name"), Some(Some("Alice")));
+        assert_eq!(rows[0].get("age"), Some(Some("30")));
+        assert_eq!(rows[1].get("age"), Some(None));
+        assert_eq!(rows[1].get("height"), None);
+        assert_eq!(rows[1].get_by_index(0), Some(Some("Bob")));
+        assert_eq!(rows[1].index(), 2);
+    }
+
+    #[test]
+    fn empty_document() {
+        let reader = RsvReader::new(&[]).with_headers().unwrap();
+        assert!(reader.headers().is_empty());
+        assert!(reader.rows().next().is_none());
+    }
+
+    #[test]
+    fn rejects_bad_headers() {
+        let buffer = encode_rsv([[Some("a"), Some("b"), Some("a")]]);
+        assert!(matches!(
+            RsvReader::new(&buffer).with_headers(),
+            Err(Error::DuplicateHeader { first: 0, second: 2, ref name }) if name == "a"
+        ));
+
+        let buffer = encode_rsv([[Some("a"), None]]);
+        assert!(matches!(
+            RsvReader::new(&buffer).with_headers(),
+            Err(Error::NullHeader { column: 1, .. })
+        ));
+    }
+
+    #[test]
+    fn rejects_ragged_rows() {
+        let buffer = encode_rsv(vec![vec![Some("a"), Some("b")], vec![Some("1")]]);
+        let reader = RsvReader::new(&buffer).with_headers().unwrap();
+        assert!(matches!(
+            reader.rows().next(),
+            Some(Err(Error::RowWidthMismatch {
+                offset: 5,
+                row: 1,
+                expected: 2,
+                found: 1
+            }))
+        ));
+    }
+}
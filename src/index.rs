@@ -0,0 +1,328 @@
+use crate::{rows_at, Error, RsvReader, RsvRow, END_ROW};
+use alloc::vec::Vec;
+use core::ops::Range;
+#[cfg(feature = "std")]
+use std::{
+    convert::TryInto,
+    io::{self, Read, Write},
+};
+
+/// Identifies a serialized `RsvIndex`.
+#[cfg(feature = "std")]
+const MAGIC: &[u8; 4] = b"RSVI";
+/// The version of the serialized format.
+#[cfg(feature = "std")]
+const VERSION: u8 = 1;
+
+/// An index of the byte offsets at which rows start in an RSV document, allowing rows to be
+/// accessed without scanning every row before them.
+///
+/// The offset of every `stride`-th row is recorded, so accessing a row scans at most `stride - 1`
+/// other rows. A stride of 1 records every row, at a cost of 8 bytes per row once serialized.
+///
+/// An index can be saved alongside its document with `write_to`, and loaded again with `read_from`.
+///
+/// # Example:
+/// ```
+/// use librsv::{RsvIndex, RsvReader};
+///
+/// let buffer = b"a\xFF\xFDb\xFF\xFDc\xFF\xFDd\xFF\xFD";
+/// let index = RsvIndex::with_stride(buffer, 2)?;
+/// let reader = RsvReader::new(buffer);
+/// let reader = reader.with_index(&index)?;
+///
+/// let row = reader.row(2)?.unwrap();
+/// assert_eq!(row.values().next().unwrap()?, Some("c"));
+/// # Ok::<(), librsv::Error>(())
+/// ```
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct RsvIndex {
+    stride: usize,
+    rows: usize,
+    data_len: usize,
+    offsets: Vec<usize>,
+}
+
+impl RsvIndex {
+    /// Builds an index recording the offset of every row.
+    pub fn build(data: &[u8]) -> Result<Self, Error> {
+        Self::with_stride(data, 1)
+    }
+
+    /// Builds an index recording the offset of every `stride`-th row.
+    ///
+    /// Only row terminators are checked, so the values of each row may still be invalid.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `stride` is zero.
+    pub fn with_stride(data: &[u8], stride: usize) -> Result<Self, Error> {
+        assert!(stride > 0, "index stride must be greater than zero");
+        let mut offsets = Vec::new();
+        let mut rows = 0;
+        for row in rows_at(data, 0, 0) {
+            let row = row?;
+            if row.index() % stride == 0 {
+                offsets.push(row.offset());
+            }
+            rows += 1;
+        }
+        Ok(Self {
+            stride,
+            rows,
+            data_len: data.len(),
+            offsets,
+        })
+    }
+
+    /// The number of rows between each recorded offset.
+    pub fn stride(&self) -> usize {
+        self.stride
+    }
+
+    /// The number of rows in the indexed document.
+    pub fn len(&self) -> usize {
+        self.rows
+    }
+
+    /// Whether the indexed document has no rows.
+    pub fn is_empty(&self) -> bool {
+        self.rows == 0
+    }
+
+    /// Writes the index in a compact binary format, such as to a sidecar file.
+    #[cfg(feature = "std")]
+    pub fn write_to<W: Write>(&self, mut output: W) -> io::Result<()> {
+        output.write_all(MAGIC)?;
+        output.write_all(&[VERSION])?;
+        for value in [self.stride, self.rows, self.data_len] {
+            output.write_all(&(value as u64).to_le_bytes())?;
+        }
+        for &offset in &self.offsets {
+            output.write_all(&(offset as u64).to_le_bytes())?;
+        }
+        output.flush()
+    }
+
+    /// Reads an index written by `write_to`.
+    ///
+    /// Returns an error of kind `InvalidData` if the input is not a valid index.
+    #[cfg(feature = "std")]
+    pub fn read_from<R: Read>(mut input: R) -> io::Result<Self> {
+        let invalid = |msg: &str| io::Error::new(io::ErrorKind::InvalidData, msg.to_string());
+
+        let mut header = [0; 5];
+        input.read_exact(&mut header)?;
+        if &header[..4] != MAGIC {
+            return Err(invalid("not an RSV index"));
+        }
+        if header[4] != VERSION {
+            return Err(invalid("unsupported RSV index version"));
+        }
+
+        let mut read_usize = || -> io::Result<usize> {
+            let mut bytes = [0; 8];
+            input.read_exact(&mut bytes)?;
+            u64::from_le_bytes(bytes)
+                .try_into()
+                .map_err(|_| invalid("offset too large for this platform"))
+        };
+        let stride = read_usize()?;
+        let rows = read_usize()?;
+        let data_len = read_usize()?;
+        if stride == 0 {
+            return Err(invalid("index stride must be greater than zero"));
+        }
+
+        // The row count is untrusted, so the offsets are not preallocated
+        let mut offsets = Vec::new();
+        for _ in 0..rows.div_ceil(stride) {
+            let offset = read_usize()?;
+            let ascending = offsets.last().map_or(offset == 0, |&last| offset > last);
+            if !ascending || offset >= data_len {
+                return Err(invalid("index offsets are out of order"));
+            }
+            offsets.push(offset);
+        }
+        Ok(Self {
+            stride,
+            rows,
+            data_len,
+            offsets,
+        })
+    }
+}
+
+impl<'a> RsvReader<'a> {
+    /// Uses the given index to access rows by their position.
+    ///
+    /// Returns an error if the index was built for a document of a different length, or if any
+    /// offset it records is not the start of a row in this document. Only the recorded offsets are
+    /// checked, so this takes time proportional to their number, rather than the document's length.
+    pub fn with_index<'i>(&self, index: &'i RsvIndex) -> Result<RsvIndexedReader<'a, 'i>, Error> {
+        if index.data_len != self.data.len() {
+            return Err(Error::IndexMismatch {
+                expected: index.data_len,
+                found: self.data.len(),
+            });
+        }
+        // Every row starts at the beginning of the document or after a row terminator, and the
+        // document ends after its last row, so an index for another document of the same length
+        // cannot be used to slice rows at arbitrary offsets
+        let ends = (index.rows > 0).then_some((self.data.len(), index.rows));
+        let starts = index.offsets.iter().enumerate();
+        let starts = starts.map(|(i, &offset)| (offset, i * index.stride));
+        for (offset, row) in starts.chain(ends) {
+            if offset > 0 && self.data[offset - 1] != END_ROW {
+                return Err(Error::IndexRowMismatch { offset, row });
+            }
+        }
+        Ok(RsvIndexedReader {
+            data: self.data,
+            index,
+        })
+    }
+}
+
+/// Reads an RSV document, using an `RsvIndex` to access rows by their position.
+pub struct RsvIndexedReader<'a, 'i> {
+    data: &'a [u8],
+    index: &'i RsvIndex,
+}
+
+impl<'a, 'i> RsvIndexedReader<'a, 'i> {
+    /// The index in use.
+    pub fn index(&self) -> &'i RsvIndex {
+        self.index
+    }
+
+    /// The number of rows in the document.
+    pub fn len(&self) -> usize {
+        self.index.rows
+    }
+
+    /// Whether the document has no rows.
+    pub fn is_empty(&self) -> bool {
+        self.index.rows == 0
+    }
+
+    /// Returns the row at the given position, or `None` if it is past the end of the document.
+    pub fn row(&self, n: usize) -> Result<Option<RsvRow<'a>>, Error> {
+        self.rows_range(n..n.saturating_add(1)).next().transpose()
+    }
+
+    /// Iterates over the rows in the given range of positions.
+    ///
+    /// The range is truncated to the end of the document.
+    pub fn rows_range(
+        &self,
+        range: Range<usize>,
+    ) -> impl Iterator<Item = Result<RsvRow<'a>, Error>> {
+        let end = range.end.min(self.index.rows);
+        let start = range.start.min(end);
+        let (data, offset, first) = match self.index.offsets.get(start / self.index.stride) {
+            Some(&offset) => (
+                &self.data[offset..],
+                offset,
+                start / self.index.stride * self.index.stride,
+            ),
+            None => (&[][..], self.data.len(), start),
+        };
+        rows_at(data, offset, first)
+            .skip(start - first)
+            .take(end - start)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::encode_rsv;
+    use alloc::string::{String, ToString};
+    use alloc::vec;
+
+    fn document(rows: usize) -> Vec<u8> {
+        encode_rsv(
+            (0..rows)
+                .map(|i| vec![Some(i.to_string())])
+                .collect::<Vec<_>>(),
+        )
+    }
+
+    fn first_value(row: RsvRow) -> String {
+        row.values().next().unwrap().unwrap().unwrap().to_string()
+    }
+
+    #[test]
+    fn random_access() {
+        let data = document(100);
+        let reader = RsvReader::new(&data);
+        for stride in [1, 3, 7, 100, 1000] {
+            let index = RsvIndex::with_stride(&data, stride).unwrap();
+            let reader = reader.with_index(&index).unwrap();
+            assert_eq!(reader.len(), 100);
+            for n in [0, 1, 2, 3, 50, 98, 99] {
+                let row = reader.row(n).unwrap().unwrap();
+                assert_eq!(row.index(), n);
+                assert_eq!(first_value(row), n.to_string());
+            }
+            assert!(reader.row(100).unwrap().is_none());
+
+            let rows = reader
+                .rows_range(95..200)
+                .map(|row| first_value(row.unwrap()))
+                .collect::<Vec<_>>();
+            assert_eq!(rows, ["95", "96", "97", "98", "99"]);
+            assert_eq!(reader.rows_range(40..40).count(), 0);
+            assert_eq!(reader.rows_range(150..160).count(), 0);
+        }
+    }
+
+    #[test]
+    #[cfg(feature = "std")]
+    fn serialization_roundtrip() {
+        let data = document(50);
+        let index = RsvIndex::with_stride(&data, 4).unwrap();
+        let mut sidecar = vec![];
+        index.write_to(&mut sidecar).unwrap();
+        assert_eq!(sidecar.len(), 5 + 8 * 3 + 8 * 13);
+        assert_eq!(RsvIndex::read_from(&sidecar[..]).unwrap(), index);
+
+        sidecar[0] = b'X';
+        let err = RsvIndex::read_from(&sidecar[..]).unwrap_err();
+        assert_eq!(err.kind(), io::ErrorKind::InvalidData);
+
+        let empty = RsvIndex::build(&[]).unwrap();
+        let mut sidecar = vec![];
+        empty.write_to(&mut sidecar).unwrap();
+        assert_eq!(RsvIndex::read_from(&sidecar[..]).unwrap(), empty);
+    }
+
+    #[test]
+    fn rejects_mismatched_documents() {
+        assert!(matches!(
+            RsvIndex::build(b"a\xFF\xFDb"),
+            Err(Error::UnterminatedRow { offset: 3, row: 1 })
+        ));
+        let index = RsvIndex::build(&document(10)).unwrap();
+        let other = document(11);
+        assert!(matches!(
+            RsvReader::new(&other).with_index(&index),
+            Err(Error::IndexMismatch { .. })
+        ));
+
+        // Documents of the same length, but with rows in different places
+        let index = RsvIndex::build(b"a\xFF\xFDbc\xFF\xFD").unwrap();
+        assert!(matches!(
+            RsvReader::new(b"ab\xFF\xFDc\xFF\xFD").with_index(&index),
+            Err(Error::IndexRowMismatch { offset: 3, row: 1 })
+        ));
+        assert!(matches!(
+            RsvReader::new(b"a\xFF\xFDbc\xFF\xFF").with_index(&index),
+            Err(Error::IndexRowMismatch { offset: 7, row: 2 })
+        ));
+        assert!(RsvReader::new(b"x\xFF\xFDyz\xFF\xFD")
+            .with_index(&index)
+            .is_ok());
+    }
+}
@@ -53,9 +53,118 @@
 //!     }
 //! }
 //! ```
+//!
+//! To process a large document without holding it in memory, `RsvStreamWriter` writes directly
+//! to any `std::io::Write` sink, and `RsvStreamReader` reads rows one at a time from any `std::io::BufRead` source.
+//! With the `tokio` feature enabled, `AsyncRsvWriter` and `AsyncRsvReader` do the same for asynchronous I/O.
+//! Where allocating is not an option, `RsvSliceWriter` writes into a fixed buffer, which can be
+//! sized exactly with `encoded_len`.
+//!
+//! Values can be parsed into other types with `RsvRow::get_as`, or a whole row at once with `RsvRow::decode`,
+//! such as `row.decode::<(i64, Option<f64>, String)>()`.
+//!
+//! To keep a decoded document in memory, `RsvDocument` stores all of its values in a single buffer,
+//! rather than allocating each one separately as `decode_rsv` does. `OwnedRow` does the same for a single row.
+//!
+//! For documents whose first row contains column names, `RsvReader::with_headers` allows values
+//! to be looked up by name. A `Schema` declares the columns a document must have, which
+//! `RsvReader::rows_with_schema` and `RsvSchemaWriter` enforce. To jump straight to a row of a large document, build an `RsvIndex`
+//! of row offsets, which can be saved alongside the document, and use `RsvReader::with_index`.
+//!
+//! Values which are not valid UTF-8, such as Latin-1 text, can be read with `RsvRow::values_bytes`
+//! and written with `push_bytes`. Alternatively, `RsvRow::values_lossy` and `decode_rsv_lossy`
+//! replace invalid UTF-8 with U+FFFD, rather than failing. To salvage what remains of a corrupted
+//! document, `RsvReader::rows_recovering` skips malformed rows and reports where they were.
+//!
+//! # Serde
+//!
+//! With the `serde` feature enabled, `to_vec`, `to_writer`, `from_slice` and `from_reader` convert
+//! between RSV documents and sequences of rows, where each row is a struct or tuple.
+//!
+//! # Cargo features
+//!
+//! * `std` (default) - Enables everything which depends on the standard library, such as streaming
+//!   and conversion. Without it, the crate is `no_std`.
+//! * `alloc` - Enables everything which needs to allocate, such as `RsvWriter` and `decode_rsv`,
+//!   without the rest of the standard library. `RsvReader` and `RsvRow` need neither feature.
+//! * `serde` - Enables serialization and deserialization using `serde`.
+//! * `rayon` - Enables `decode_rsv_parallel`, which decodes large documents on multiple threads.
+//! * `tokio` - Enables `AsyncRsvReader` and `AsyncRsvWriter`, for use with `tokio`.
+//! * `mmap` - Enables `RsvMmap`, which reads files by mapping them into memory.
+//! * `memchr` - Uses the `memchr` crate to search for terminator bytes with SIMD instructions.
 
+#![cfg_attr(not(feature = "std"), no_std)]
+
+#[cfg(feature = "alloc")]
+extern crate alloc;
+
+#[cfg(feature = "alloc")]
+use alloc::{string::String, vec::Vec};
+#[cfg(feature = "alloc")]
+use encode::format_value;
 use thiserror::Error;
 
+#[cfg(feature = "tokio")]
+mod async_stream;
+#[cfg(feature = "std")]
+pub mod convert;
+#[cfg(feature = "serde")]
+mod de;
+mod encode;
+#[cfg(feature = "alloc")]
+mod headers;
+#[cfg(feature = "alloc")]
+mod index;
+#[cfg(feature = "alloc")]
+mod lossy;
+#[cfg(feature = "mmap")]
+mod mmap;
+#[cfg(feature = "alloc")]
+mod owned;
+#[cfg(feature = "rayon")]
+mod parallel;
+#[cfg(feature = "alloc")]
+mod recover;
+mod scan;
+#[cfg(feature = "alloc")]
+mod schema;
+#[cfg(feature = "serde")]
+mod ser;
+mod slice;
+#[cfg(feature = "std")]
+mod stream;
+#[cfg(feature = "alloc")]
+mod typed;
+
+#[cfg(feature = "tokio")]
+pub use async_stream::{AsyncRsvReader, AsyncRsvWriter};
+#[cfg(feature = "serde")]
+pub use de::{from_reader, from_slice};
+pub use encode::{IntoRsvRow, IntoRsvValue};
+#[cfg(feature = "alloc")]
+pub use headers::{RsvHeaderReader, RsvHeaderRow, RsvHeaders};
+#[cfg(feature = "alloc")]
+pub use index::{RsvIndex, RsvIndexedReader};
+#[cfg(feature = "alloc")]
+pub use lossy::{decode_rsv_lossy, LossyDocument, Repair};
+#[cfg(feature = "mmap")]
+pub use mmap::RsvMmap;
+#[cfg(feature = "alloc")]
+pub use owned::{OwnedRow, RsvDocument, RsvDocumentRow};
+#[cfg(feature = "rayon")]
+pub use parallel::{decode_rsv_parallel, split_rows};
+#[cfg(feature = "alloc")]
+pub use recover::{Diagnostic, RecoveringRows};
+#[cfg(feature = "alloc")]
+pub use schema::{Column, RsvSchemaWriter, Schema, SchemaRule};
+#[cfg(feature = "serde")]
+pub use ser::{to_vec, to_writer};
+pub use slice::{encoded_len, RsvSliceWriter};
+#[cfg(feature = "std")]
+pub use stream::{RsvStreamReader, RsvStreamWriter};
+#[cfg(feature = "alloc")]
+pub use typed::{FromRow, FromValue, ValueError};
+
 /// Row termination byte.
 const END_ROW: u8 = 0xFD;
 /// Represents an absent value.
@@ -64,26 +173,283 @@ const NULL_VALUE: u8 = 0xFE;
 const END_VALUE: u8 = 0xFF;
 
 /// An error encountered while parsing an RSV stream.
-#[derive(Error, Debug, Clone, Copy, PartialEq, Eq)]
+///
+/// Decoding errors report the byte offset in the input at which the problem was found,
+/// along with the zero-based index of the offending row and, where applicable, value.
+///
+/// Some variants own strings, so unlike in version 0.1, `Error` is `Clone` but not `Copy`.
+#[derive(Error, Debug, Clone, PartialEq, Eq)]
 pub enum Error {
     /// The input ended without a row terminator byte.
-    #[error("unexpected end of input, expected a row terminator")]
-    UnterminatedRow,
+    #[error("unexpected end of input in row {row} (starting at byte {offset}), expected a row terminator")]
+    UnterminatedRow {
+        /// The byte offset at which the unterminated row starts.
+        offset: usize,
+        /// The index of the unterminated row.
+        row: usize,
+    },
     /// The row ended without a value terminator byte.
-    #[error("unexpected end of row, expected a value terminator")]
-    UnterminatedValue,
+    #[error("unexpected end of row {row} in value {value} (starting at byte {offset}), expected a value terminator")]
+    UnterminatedValue {
+        /// The byte offset at which the unterminated value starts.
+        offset: usize,
+        /// The index of the row containing the value.
+        row: usize,
+        /// The index of the unterminated value within its row.
+        value: usize,
+    },
     /// A value contained invalid UTF-8.
-    #[error("a value contained invalid UTF-8: {0}")]
-    BadUTF8(std::str::Utf8Error),
+    #[error("invalid UTF-8 at byte {offset} in row {row}, value {value}: {source}")]
+    BadUTF8 {
+        /// The byte offset of the first invalid byte.
+        offset: usize,
+        /// The index of the row containing the value.
+        row: usize,
+        /// The index of the value within its row.
+        value: usize,
+        /// The underlying UTF-8 error, relative to the start of the value.
+        source: core::str::Utf8Error,
+    },
+    /// A header row contained a null column name.
+    #[error("null column name in header row (starting at byte {offset}), column {column}")]
+    NullHeader {
+        /// The byte offset at which the header row starts.
+        offset: usize,
+        /// The index of the unnamed column.
+        column: usize,
+    },
+    /// A header row contained the same column name more than once.
+    #[cfg(feature = "alloc")]
+    #[error("duplicate column name {name:?} in header row, columns {first} and {second}")]
+    DuplicateHeader {
+        /// The duplicated column name.
+        name: String,
+        /// The index of the first column with this name.
+        first: usize,
+        /// The index of the second column with this name.
+        second: usize,
+    },
+    /// A row had a different number of values to the header row.
+    #[error("row {row} (starting at byte {offset}) has {found} values, expected {expected}")]
+    RowWidthMismatch {
+        /// The byte offset at which the row starts.
+        offset: usize,
+        /// The index of the row.
+        row: usize,
+        /// The number of values expected.
+        expected: usize,
+        /// The number of values found.
+        found: usize,
+    },
+    /// A row had no value at the requested index.
+    #[error("row {row} (starting at byte {offset}) has no value {value}")]
+    MissingValue {
+        /// The byte offset at which the row starts.
+        offset: usize,
+        /// The index of the row.
+        row: usize,
+        /// The index of the requested value.
+        value: usize,
+    },
+    /// A value was null, but the type it was being parsed as cannot represent null.
+    #[error(
+        "row {row}, value {value} (at byte {offset}) is null{}",
+        ExpectedType(", expected ", *.expected)
+    )]
+    NullValue {
+        /// The byte offset at which the value starts.
+        offset: usize,
+        /// The index of the row containing the value.
+        row: usize,
+        /// The index of the value within its row.
+        value: usize,
+        /// The name of the type the value was being parsed as, if it is known.
+        ///
+        /// This is `FromValue::TYPE_NAME` when decoding a row, and `None` for `RsvRow::get_as`,
+        /// which accepts any `FromStr` type.
+        expected: Option<&'static str>,
+    },
+    /// A value could not be parsed as the requested type.
+    #[cfg(feature = "alloc")]
+    #[error(
+        "row {row}, value {value} (at byte {offset}) could not be parsed{}: {message}",
+        ExpectedType(" as ", *.expected)
+    )]
+    ParseValue {
+        /// The byte offset at which the value starts.
+        offset: usize,
+        /// The index of the row containing the value.
+        row: usize,
+        /// The index of the value within its row.
+        value: usize,
+        /// The name of the type the value was being parsed as, if it is known, as for `NullValue`.
+        expected: Option<&'static str>,
+        /// The error message from parsing the value.
+        message: String,
+    },
+    /// A value broke a rule of a `Schema`.
+    #[cfg(feature = "alloc")]
+    #[error("row {row}, column {column} ({name:?}) {rule}")]
+    SchemaViolation {
+        /// The index of the row.
+        row: usize,
+        /// The index of the column.
+        column: usize,
+        /// The name of the column.
+        name: String,
+        /// The rule which was broken.
+        rule: SchemaRule,
+    },
+    /// An index was used with a different document to the one it was built for.
+    #[error("index was built for a document of {expected} bytes, but the document has {found}")]
+    IndexMismatch {
+        /// The length of the document the index was built for.
+        expected: usize,
+        /// The length of the document the index was used with.
+        found: usize,
+    },
+    /// An index recorded a row at an offset where no row starts in the document it was used with.
+    #[error("index records row {row} at byte {offset}, but no row starts there")]
+    IndexRowMismatch {
+        /// The offset recorded by the index.
+        offset: usize,
+        /// The position of the row recorded at that offset.
+        row: usize,
+    },
+    /// An I/O error occurred while reading from a stream.
+    #[error("I/O error: {0}")]
+    #[cfg(feature = "std")]
+    Io(#[from] IoError),
+    /// A value could not be serialized or deserialized.
+    #[cfg(feature = "serde")]
+    #[error("{0}")]
+    Message(String),
+}
+
+#[cfg(feature = "std")]
+impl From<std::io::Error> for Error {
+    fn from(err: std::io::Error) -> Self {
+        Error::Io(err.into())
+    }
+}
+
+/// An I/O error which, unlike `std::io::Error`, can be cloned and compared.
+///
+/// Clones share the same underlying error. Two errors are equal if they have the same kind and message.
+#[cfg(feature = "std")]
+#[derive(Clone, Debug)]
+pub struct IoError(std::sync::Arc<std::io::Error>);
+
+#[cfg(feature = "std")]
+impl IoError {
+    /// The kind of the underlying error.
+    pub fn kind(&self) -> std::io::ErrorKind {
+        self.0.kind()
+    }
+
+    /// Returns a reference to the underlying error.
+    pub fn get_ref(&self) -> &std::io::Error {
+        &self.0
+    }
+}
+
+#[cfg(feature = "std")]
+impl From<std::io::Error> for IoError {
+    fn from(err: std::io::Error) -> Self {
+        Self(std::sync::Arc::new(err))
+    }
+}
+
+#[cfg(feature = "std")]
+impl PartialEq for IoError {
+    fn eq(&self, other: &Self) -> bool {
+        std::sync::Arc::ptr_eq(&self.0, &other.0)
+            || (self.kind() == other.kind() && self.0.to_string() == other.0.to_string())
+    }
+}
+
+#[cfg(feature = "std")]
+impl Eq for IoError {}
+
+#[cfg(feature = "std")]
+impl core::fmt::Display for IoError {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        self.0.fmt(f)
+    }
+}
+
+#[cfg(feature = "std")]
+impl std::error::Error for IoError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        self.0.source()
+    }
+}
+
+/// An error encountered while writing an RSV document.
+#[derive(Error, Debug, Clone, Copy, PartialEq, Eq)]
+pub enum WriteError {
+    /// A value contained one of the bytes reserved for RSV's terminators and null marker.
+    #[error("value contains the reserved byte {byte:#04X} at position {position}")]
+    ReservedByte {
+        /// The reserved byte.
+        byte: u8,
+        /// The position of the reserved byte within the value.
+        position: usize,
+    },
+    /// A value was pushed before any row was started.
+    #[error("must start a row before pushing a value")]
+    RowNotStarted,
+    /// The output buffer of an `RsvSliceWriter` did not have enough space left.
+    #[error("buffer full: {needed} bytes needed, but only {available} available")]
+    BufferFull {
+        /// The number of bytes needed.
+        needed: usize,
+        /// The number of bytes left in the buffer.
+        available: usize,
+    },
+}
+
+/// Displays the name of the type a value was expected to be, after a prefix, if it is known.
+struct ExpectedType(&'static str, Option<&'static str>);
+
+impl core::fmt::Display for ExpectedType {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        match self.1 {
+            Some(name) => write!(f, "{}{}", self.0, name),
+            None => Ok(()),
+        }
+    }
+}
+
+impl Error {
+    /// Shifts the row index of a decoding error, for errors found in a chunk of a larger document.
+    #[cfg(feature = "rayon")]
+    pub(crate) fn offset_rows(mut self, rows: usize) -> Self {
+        match &mut self {
+            Error::UnterminatedRow { row, .. }
+            | Error::UnterminatedValue { row, .. }
+            | Error::BadUTF8 { row, .. }
+            | Error::RowWidthMismatch { row, .. }
+            | Error::MissingValue { row, .. }
+            | Error::NullValue { row, .. }
+            | Error::ParseValue { row, .. }
+            | Error::SchemaViolation { row, .. } => *row += rows,
+            _ => {}
+        }
+        self
+    }
 }
 
 /// A convenience method for encoding an RSV document.
 ///
-/// The generic parameters allow for encoding a variety of owned or borrowed data structures, such as:
+/// The rows can be any iterator or collection of rows implementing `IntoRsvRow`, which includes any
+/// iterator or collection of values implementing `IntoRsvValue`. This allows for encoding a variety
+/// of owned or borrowed data structures, such as:
 /// * `Vec<Vec<Option<String>>>`
-/// * `Vec<Vec<Option<str>>>`
-/// * `Vec<&[Option<str>]>`
-/// * `&[&[Option<str>]]`
+/// * `&Vec<Vec<Option<&str>>>`
+/// * `&[&[Option<&str>]]`
+/// * `Vec<&[Option<&str>]>`
+/// * `Vec<Vec<String>>` or `[[i64; 3]; 2]`, which contain no nulls
 ///
 /// # Example:
 /// ```
@@ -93,20 +459,19 @@ pub enum Error {
 /// ]);
 ///
 /// assert_eq!(&buffer, b"Hello\xFFworld\xFF\xFD\xFE\xFFasdf\xFF\xFD");
+///
+/// let rows = (1..=2).map(|i| vec![i, i * 10]);
+///
+/// assert_eq!(&librsv::encode_rsv(rows), b"1\xFF10\xFF\xFD2\xFF20\xFF\xFD");
 /// ```
-pub fn encode_rsv<T, R, V>(rows: T) -> Vec<u8>
+#[cfg(feature = "alloc")]
+pub fn encode_rsv<T, R, M>(rows: T) -> Vec<u8>
 where
-    T: AsRef<[R]>,
-    R: AsRef<[Option<V>]>,
-    V: AsRef<str>,
+    T: IntoIterator<Item = R>,
+    R: IntoRsvRow<M>,
 {
     let mut writer = RsvWriter::new();
-    for row in rows.as_ref() {
-        writer.start_row();
-        for value in row.as_ref() {
-            writer.push(value.as_ref().map(|v| v.as_ref()))
-        }
-    }
+    writer.write_rows(rows);
     writer.finish()
 }
 
@@ -119,19 +484,27 @@ where
 ///
 /// assert_eq!(data, vec![vec![Some("Hello".into()), Some("world".into())]]);
 /// ```
+#[cfg(feature = "alloc")]
 pub fn decode_rsv(data: &[u8]) -> Result<Vec<Vec<Option<String>>>, Error> {
-    RsvReader::new(data)
-        .rows()
-        .map(|row| {
-            row?.values()
-                .map(|v| v.map(|v| v.map(|v| v.to_string())))
-                .collect::<Result<_, _>>()
-        })
-        .collect::<Result<_, _>>()
+    decode_rows(RsvReader::new(data).rows())
+}
+
+/// Decodes each of the given rows into a `Vec<Option<String>>`.
+#[cfg(feature = "alloc")]
+pub(crate) fn decode_rows<'a>(
+    rows: impl Iterator<Item = Result<RsvRow<'a>, Error>>,
+) -> Result<Vec<Vec<Option<String>>>, Error> {
+    rows.map(|row| {
+        row?.values()
+            .map(|v| v.map(|v| v.map(String::from)))
+            .collect::<Result<_, _>>()
+    })
+    .collect::<Result<_, _>>()
 }
 
 /// A convenience method for decoding an RSV document into a `Vec<Vec<Option<&str>>>`,
 /// with the string values borrowing from the encoded bytes.
+#[cfg(feature = "alloc")]
 pub fn decode_rsv_borrowed(data: &[u8]) -> Result<Vec<Vec<Option<&str>>>, Error> {
     RsvReader::new(data)
         .rows()
@@ -140,16 +513,19 @@ pub fn decode_rsv_borrowed(data: &[u8]) -> Result<Vec<Vec<Option<&str>>>, Error>
 }
 
 /// Writes an RSV document to an internal `Vec<u8>`.
+#[cfg(feature = "alloc")]
 #[derive(Clone, Default)]
 pub struct RsvWriter {
     buffer: Vec<u8>,
     started_row: bool,
+    auto_start_row: bool,
 }
 
+#[cfg(feature = "alloc")]
 impl RsvWriter {
     /// Creates a new `RsvWriter`.
     pub fn new() -> Self {
-        Self::with_buffer(vec![])
+        Self::with_buffer(Vec::new())
     }
 
     /// Creates a new `RsvWriter`, with a given initial capacity.
@@ -167,9 +543,26 @@ impl RsvWriter {
         }
     }
 
+    /// Sets whether pushing a value before any row has been started starts one automatically.
+    ///
+    /// Otherwise, `push` panics and the fallible methods return `WriteError::RowNotStarted`.
+    /// Defaults to `false`.
+    ///
+    /// # Example:
+    /// ```
+    /// let mut writer = librsv::RsvWriter::new().auto_start_row(true);
+    /// writer.push_str("Hello");
+    ///
+    /// assert_eq!(&writer.finish(), b"Hello\xFF\xFD");
+    /// ```
+    pub fn auto_start_row(mut self, auto_start_row: bool) -> Self {
+        self.auto_start_row = auto_start_row;
+        self
+    }
+
     /// Begins a new row.
     ///
-    /// This must be called before pushing any values.
+    /// This must be called before pushing any values, unless `auto_start_row` is enabled.
     pub fn start_row(&mut self) {
         if self.started_row {
             self.buffer.push(END_ROW);
@@ -177,14 +570,86 @@ impl RsvWriter {
         self.started_row = true;
     }
 
+    /// Begins a new row, like `start_row`.
+    ///
+    /// This cannot fail for an `RsvWriter`, but has the same signature as `RsvSliceWriter::start_row`,
+    /// so that code written against the fallible methods can use either writer.
+    pub fn try_start_row(&mut self) -> Result<(), WriteError> {
+        self.start_row();
+        Ok(())
+    }
+
     /// Pushes a value to the current row.
+    ///
+    /// # Panics
+    ///
+    /// Panics if no row has been started and `auto_start_row` is not enabled. Use `try_push`
+    /// to handle this as an error instead.
     pub fn push(&mut self, value: Option<&str>) {
-        assert!(self.started_row, "must start a row before pushing a value");
+        if let Err(err) = self.try_push(value) {
+            panic!("{}", err);
+        }
+    }
+
+    /// Pushes a value to the current row, or returns `WriteError::RowNotStarted` if there is none.
+    ///
+    /// # Example:
+    /// ```
+    /// use librsv::{RsvWriter, WriteError};
+    ///
+    /// let mut writer = RsvWriter::new();
+    /// assert_eq!(writer.try_push(Some("Hello")), Err(WriteError::RowNotStarted));
+    /// writer.try_start_row()?;
+    /// writer.try_push(Some("Hello"))?;
+    ///
+    /// assert_eq!(&writer.finish(), b"Hello\xFF\xFD");
+    /// # Ok::<(), WriteError>(())
+    /// ```
+    pub fn try_push(&mut self, value: Option<&str>) -> Result<(), WriteError> {
+        self.check_row()?;
         match value {
             Some(str) => self.buffer.extend(str.as_bytes()),
             None => self.buffer.push(NULL_VALUE),
         }
         self.buffer.push(END_VALUE);
+        Ok(())
+    }
+
+    /// Pushes a value of raw bytes to the current row, which need not be valid UTF-8.
+    ///
+    /// Values containing the bytes `0xFD`, `0xFE` or `0xFF` cannot be represented in RSV, so are
+    /// rejected with `WriteError::ReservedByte`, in which case nothing is written. Like `try_push`,
+    /// this returns `WriteError::RowNotStarted` if there is no row to push to.
+    ///
+    /// # Example:
+    /// ```
+    /// use librsv::{RsvWriter, WriteError};
+    ///
+    /// let mut writer = RsvWriter::new();
+    /// writer.start_row();
+    /// writer.push_bytes(Some(b"caf\xE9"))?;
+    /// assert_eq!(
+    ///     writer.push_bytes(Some(b"\xFF")),
+    ///     Err(WriteError::ReservedByte { byte: 0xFF, position: 0 })
+    /// );
+    ///
+    /// assert_eq!(&writer.finish(), b"caf\xE9\xFF\xFD");
+    /// # Ok::<(), WriteError>(())
+    /// ```
+    pub fn push_bytes(&mut self, value: Option<&[u8]>) -> Result<(), WriteError> {
+        match value {
+            Some(bytes) => {
+                check_bytes(bytes)?;
+                self.check_row()?;
+                self.buffer.extend(bytes);
+            }
+            None => {
+                self.check_row()?;
+                self.buffer.push(NULL_VALUE);
+            }
+        }
+        self.buffer.push(END_VALUE);
+        Ok(())
     }
 
     /// Pushes a string value to the current row.
@@ -197,6 +662,57 @@ impl RsvWriter {
         self.push(None)
     }
 
+    /// Writes a whole row, starting a new row and pushing each value in turn.
+    ///
+    /// The values can come from any iterator, so rows can be written as they are produced, without
+    /// first collecting them. The row can be of any type implementing `IntoRsvRow`, which includes
+    /// any iterator of `Option<impl AsRef<str>>`, such as the `Option<Box<str>>` or
+    /// `Option<Arc<str>>` values produced by a database cursor.
+    ///
+    /// # Example:
+    /// ```
+    /// let mut writer = librsv::RsvWriter::new();
+    /// writer.write_row(vec![Some("Hello"), None]);
+    /// writer.write_row((1..=2).map(|i| Some(i.to_string())));
+    ///
+    /// assert_eq!(&writer.finish(), b"Hello\xFF\xFE\xFF\xFD1\xFF2\xFF\xFD");
+    /// ```
+    pub fn write_row<R, M>(&mut self, row: R)
+    where
+        R: IntoRsvRow<M>,
+    {
+        self.start_row();
+        for value in row.into_rsv_row() {
+            match value.into_rsv_value() {
+                Some(value) => format_value(value, |bytes| self.buffer.extend(bytes)),
+                None => self.buffer.push(NULL_VALUE),
+            }
+            self.buffer.push(END_VALUE);
+        }
+    }
+
+    /// Writes each of the given rows, as with `write_row`.
+    pub fn write_rows<T, R, M>(&mut self, rows: T)
+    where
+        T: IntoIterator<Item = R>,
+        R: IntoRsvRow<M>,
+    {
+        for row in rows {
+            self.write_row(row);
+        }
+    }
+
+    /// Checks that there is a row to push values to, starting one if `auto_start_row` is enabled.
+    fn check_row(&mut self) -> Result<(), WriteError> {
+        if !self.started_row {
+            match self.auto_start_row {
+                true => self.started_row = true,
+                false => return Err(WriteError::RowNotStarted),
+            }
+        }
+        Ok(())
+    }
+
     /// Finishes writing and returns the inner buffer.
     pub fn finish(self) -> Vec<u8> {
         let mut buffer = self.buffer;
@@ -207,70 +723,226 @@ impl RsvWriter {
     }
 }
 
+/// Checks that a value of raw bytes contains none of the reserved bytes.
+pub(crate) fn check_bytes(value: &[u8]) -> Result<(), WriteError> {
+    match value.iter().position(|&byte| byte >= END_ROW) {
+        Some(position) => Err(WriteError::ReservedByte {
+            byte: value[position],
+            position,
+        }),
+        None => Ok(()),
+    }
+}
+
 /// Reads an RSV document.
 pub struct RsvReader<'a> {
     data: &'a [u8],
+    /// The offset and index of the next row to be read by `read_row_into`.
+    #[cfg(feature = "alloc")]
+    cursor: (usize, usize),
 }
 
 /// Reads an RSV row.
 pub struct RsvRow<'a> {
     data: &'a [u8],
+    offset: usize,
+    index: usize,
 }
 
 impl<'a> RsvReader<'a> {
     /// Creates a new `RsvReader` from the provided buffer.
     pub fn new(data: &'a [u8]) -> Self {
-        Self { data }
+        Self {
+            data,
+            #[cfg(feature = "alloc")]
+            cursor: (0, 0),
+        }
     }
 
     /// Iterates over the rows in the RSV document.
     pub fn rows(&self) -> impl Iterator<Item = Result<RsvRow<'a>, Error>> {
-        let mut remain = self.data;
-        std::iter::from_fn(move || {
-            if remain.is_empty() {
-                return None;
+        rows_at(self.data, 0, 0)
+    }
+
+    /// Reads the values of the next row into `buf`, replacing its contents, and returns `false`
+    /// once there are no rows left.
+    ///
+    /// The capacity of `buf` is reused, so reading a document with this method does not allocate
+    /// once `buf` is large enough for the widest row. The position of the next row is tracked
+    /// separately from `rows`. If the row is invalid, `buf` is left empty and the next call reads
+    /// the following row.
+    ///
+    /// # Example:
+    /// ```
+    /// let mut reader = librsv::RsvReader::new(b"a\xFFb\xFF\xFD\xFE\xFF\xFD");
+    /// let mut values = Vec::new();
+    ///
+    /// assert!(reader.read_row_into(&mut values)?);
+    /// assert_eq!(values, vec![Some("a"), Some("b")]);
+    /// assert!(reader.read_row_into(&mut values)?);
+    /// assert_eq!(values, vec![None]);
+    /// assert!(!reader.read_row_into(&mut values)?);
+    /// # Ok::<(), librsv::Error>(())
+    /// ```
+    #[cfg(feature = "alloc")]
+    pub fn read_row_into(&mut self, buf: &mut Vec<Option<&'a str>>) -> Result<bool, Error> {
+        buf.clear();
+        let (offset, index) = self.cursor;
+        let Some(row) = rows_at(&self.data[offset..], offset, index).next() else {
+            return Ok(false);
+        };
+        let row = match row {
+            Ok(row) => row,
+            Err(err) => {
+                self.cursor.0 = self.data.len();
+                return Err(err);
             }
-            let Some(terminator) = remain.iter().position(|c| *c == END_ROW) else {
-                return Some(Err(Error::UnterminatedRow));
-            };
-            let (row, rest) = remain.split_at(terminator);
-            remain = &rest[1..];
-            Some(Ok(RsvRow::new(row)))
-        })
+        };
+        self.cursor = (offset + row.data.len() + 1, index + 1);
+        for value in row.values() {
+            match value {
+                Ok(value) => buf.push(value),
+                Err(err) => {
+                    buf.clear();
+                    return Err(err);
+                }
+            }
+        }
+        Ok(true)
     }
 }
 
+/// Iterates over the rows in `data`, which starts at the given byte offset and row index within the document.
+pub(crate) fn rows_at(
+    mut remain: &[u8],
+    mut offset: usize,
+    mut index: usize,
+) -> impl Iterator<Item = Result<RsvRow<'_>, Error>> {
+    core::iter::from_fn(move || {
+        if remain.is_empty() {
+            return None;
+        }
+        let Some(terminator) = scan::find(END_ROW, remain) else {
+            remain = &[];
+            return Some(Err(Error::UnterminatedRow { offset, row: index }));
+        };
+        let (row, rest) = remain.split_at(terminator);
+        let row = RsvRow::at(row, offset, index);
+        remain = &rest[1..];
+        offset += terminator + 1;
+        index += 1;
+        Some(Ok(row))
+    })
+}
+
 impl<'a> RsvRow<'a> {
     /// Creates a new `RsvRow` from the provided buffer.
     ///
     /// This generally won't be called directly.
     pub fn new(data: &'a [u8]) -> Self {
-        Self { data }
+        Self::at(data, 0, 0)
+    }
+
+    /// Creates a new `RsvRow` from the provided buffer, which starts at the given
+    /// byte offset and row index within the document.
+    pub(crate) fn at(data: &'a [u8], offset: usize, index: usize) -> Self {
+        Self {
+            data,
+            offset,
+            index,
+        }
+    }
+
+    /// The byte offset of the start of this row within the document.
+    pub fn offset(&self) -> usize {
+        self.offset
+    }
+
+    /// The zero-based index of this row within the document.
+    pub fn index(&self) -> usize {
+        self.index
     }
 
     /// Iterates over the values in the RSV row.
     pub fn values(&self) -> impl Iterator<Item = Result<Option<&'a str>, Error>> {
+        self.located_values()
+            .map(|value| value.map(|(_, _, value)| value))
+    }
+
+    /// Iterates over the values in the RSV row, along with their byte offset and index.
+    pub(crate) fn located_values(
+        &self,
+    ) -> impl Iterator<Item = Result<(usize, usize, Option<&'a str>), Error>> {
+        let row = self.index;
+        self.raw_values().map(move |value| {
+            let (start, index, value) = value?;
+            let Some(value) = value else {
+                return Ok((start, index, None));
+            };
+            let value = core::str::from_utf8(value).map_err(|source| Error::BadUTF8 {
+                offset: start + source.valid_up_to(),
+                row,
+                value: index,
+                source,
+            })?;
+            Ok((start, index, Some(value)))
+        })
+    }
+
+    /// Iterates over the values in the RSV row as raw bytes, without checking that they are valid UTF-8.
+    ///
+    /// # Example:
+    /// ```
+    /// let row = librsv::RsvRow::new(b"caf\xE9\xFF\xFE\xFF");
+    /// let values = row.values_bytes().collect::<Result<Vec<_>, _>>()?;
+    ///
+    /// assert_eq!(values, vec![Some(&b"caf\xE9"[..]), None]);
+    /// # Ok::<(), librsv::Error>(())
+    /// ```
+    pub fn values_bytes(&self) -> impl Iterator<Item = Result<Option<&'a [u8]>, Error>> {
+        self.raw_values()
+            .map(|value| value.map(|(_, _, value)| value))
+    }
+
+    /// Iterates over the values in the RSV row, along with their byte offset and index.
+    pub(crate) fn raw_values(
+        &self,
+    ) -> impl Iterator<Item = Result<(usize, usize, Option<&'a [u8]>), Error>> {
         let mut remain = self.data;
-        std::iter::from_fn(move || {
+        let mut offset = self.offset;
+        let row = self.index;
+        let mut index = 0;
+        core::iter::from_fn(move || {
             if remain.is_empty() {
                 return None;
             }
-            let Some(terminator) = remain.iter().position(|c| *c == END_VALUE) else {
-                return Some(Err(Error::UnterminatedValue));
+            let Some(terminator) = scan::find(END_VALUE, remain) else {
+                remain = &[];
+                return Some(Err(Error::UnterminatedValue {
+                    offset,
+                    row,
+                    value: index,
+                }));
             };
             let (value, rest) = remain.split_at(terminator);
+            let start = offset;
+            let value_index = index;
             remain = &rest[1..];
-            match value {
-                [NULL_VALUE] => Some(Ok(None)),
-                bytes => Some(std::str::from_utf8(bytes).map(Some).map_err(Error::BadUTF8)),
-            }
+            offset += terminator + 1;
+            index += 1;
+            let value = match value {
+                [NULL_VALUE] => None,
+                value => Some(value),
+            };
+            Some(Ok((start, value_index, value)))
         })
     }
 }
 
-#[cfg(test)]
+#[cfg(all(test, feature = "alloc"))]
 mod tests {
     use super::*;
+    use alloc::{borrow::Cow, boxed::Box, rc::Rc, string::ToString, sync::Arc, vec};
 
     #[test]
     fn roundtrip() {
@@ -341,4 +1013,171 @@ mod tests {
         let data: &[&[Option<&str>]] = &[&values, &values[1..]];
         encode_rsv(data);
     }
+
+    #[test]
+    fn encode_as_ref_str() {
+        let boxed: Vec<Vec<Option<Box<str>>>> = vec![vec![Some("a".into()), None]];
+        assert_eq!(encode_rsv(&boxed), b"a\xFF\xFE\xFF\xFD");
+        let shared: Vec<Vec<Option<Arc<str>>>> = vec![vec![Some("a".into()), None]];
+        assert_eq!(encode_rsv(&shared), b"a\xFF\xFE\xFF\xFD");
+        let string = String::from("a");
+        let borrowed: Vec<Vec<Option<&String>>> = vec![vec![Some(&string), None]];
+        assert_eq!(encode_rsv(&borrowed), b"a\xFF\xFE\xFF\xFD");
+        let counted: &[&[Option<Rc<str>>]] = &[&[Some(Rc::from("a")), None]];
+        assert_eq!(encode_rsv(counted), b"a\xFF\xFE\xFF\xFD");
+    }
+
+    #[test]
+    fn encode_without_options() {
+        let strings = vec![vec![String::from("a"), String::new()], vec![]];
+        assert_eq!(encode_rsv(&strings), b"a\xFF\xFF\xFD\xFD");
+        assert_eq!(encode_rsv(strings), b"a\xFF\xFF\xFD\xFD");
+        assert_eq!(encode_rsv([[1.5, -2.0]].iter()), b"1.5\xFF-2\xFF\xFD");
+        let mixed = vec![Some(Cow::Borrowed("a")), None, Some(Cow::Owned("b".into()))];
+        assert_eq!(encode_rsv([&mixed]), b"a\xFF\xFE\xFFb\xFF\xFD");
+    }
+
+    #[test]
+    fn error_positions() {
+        let data = b"a\xFF\xFDbc\xFFd\xFF\xFDe";
+        let mut rows = RsvReader::new(data).rows();
+        rows.next().unwrap().unwrap();
+        let row = rows.next().unwrap().unwrap();
+        assert_eq!((row.offset(), row.index()), (3, 1));
+        assert!(matches!(
+            rows.next(),
+            Some(Err(Error::UnterminatedRow { offset: 9, row: 2 }))
+        ));
+        assert!(rows.next().is_none());
+
+        let data = b"a\xFF\xFDb\xFFc\xFD";
+        let row = RsvReader::new(data).rows().nth(1).unwrap().unwrap();
+        let mut values = row.values();
+        values.next().unwrap().unwrap();
+        assert!(matches!(
+            values.next(),
+            Some(Err(Error::UnterminatedValue {
+                offset: 5,
+                row: 1,
+                value: 1
+            }))
+        ));
+
+        let data = b"a\xFF\xFDb\xFFxy\xC3\xFF\xFD";
+        let err = decode_rsv(data).unwrap_err();
+        assert!(matches!(
+            err,
+            Error::BadUTF8 {
+                offset: 7,
+                row: 1,
+                value: 1,
+                ..
+            }
+        ));
+        assert_eq!(
+            err.to_string(),
+            "invalid UTF-8 at byte 7 in row 1, value 1: incomplete utf-8 byte sequence from index 2"
+        );
+    }
+
+    #[test]
+    fn bytes_roundtrip() {
+        let mut w = RsvWriter::new();
+        w.start_row();
+        w.push_bytes(Some(b"caf\xE9")).unwrap();
+        w.push_bytes(None).unwrap();
+        w.push_bytes(Some(b"")).unwrap();
+        for byte in [END_ROW, NULL_VALUE, END_VALUE] {
+            assert_eq!(
+                w.push_bytes(Some(&[b'a', byte])),
+                Err(WriteError::ReservedByte { byte, position: 1 })
+            );
+        }
+        let buffer = w.finish();
+        assert_eq!(&buffer, b"caf\xE9\xFF\xFE\xFF\xFF\xFD");
+
+        let row = RsvReader::new(&buffer).rows().next().unwrap().unwrap();
+        let values = row.values_bytes().collect::<Result<Vec<_>, _>>().unwrap();
+        assert_eq!(values, vec![Some(&b"caf\xE9"[..]), None, Some(&b""[..])]);
+        assert!(matches!(
+            row.values().next(),
+            Some(Err(Error::BadUTF8 { offset: 3, .. }))
+        ));
+    }
+
+    #[test]
+    fn write_rows() {
+        let rows = vec![vec![Some("a"), None], vec![], vec![Some("")]];
+        let mut w = RsvWriter::new();
+        w.write_rows(rows.iter().map(|row| row.iter().copied()));
+        assert_eq!(w.finish(), encode_rsv(&rows));
+
+        let mut w = RsvWriter::new();
+        w.write_row(vec![Some(String::from("a"))]);
+        w.write_rows(vec![vec![None::<&str>]]);
+        w.write_row(core::iter::empty::<Option<&str>>());
+        w.write_row(vec![Some(Box::<str>::from("x"))]);
+        w.write_rows((0..2).map(|_| [None, Some(Arc::<str>::from("y"))]));
+        assert_eq!(
+            w.finish(),
+            b"a\xFF\xFD\xFE\xFF\xFD\xFDx\xFF\xFD\xFE\xFFy\xFF\xFD\xFE\xFFy\xFF\xFD"
+        );
+    }
+
+    #[test]
+    fn row_not_started() {
+        let mut w = RsvWriter::new();
+        assert_eq!(w.try_push(None), Err(WriteError::RowNotStarted));
+        assert_eq!(w.push_bytes(Some(b"a")), Err(WriteError::RowNotStarted));
+        // A value with reserved bytes is reported as such, whether or not a row was started
+        assert!(matches!(
+            w.push_bytes(Some(b"\xFF")),
+            Err(WriteError::ReservedByte { .. })
+        ));
+        w.try_start_row().unwrap();
+        w.try_push(Some("a")).unwrap();
+        w.try_start_row().unwrap();
+        assert_eq!(w.finish(), b"a\xFF\xFD\xFD");
+
+        let mut w = RsvWriter::with_buffer(b"x\xFF\xFD".to_vec()).auto_start_row(true);
+        w.push_bytes(None).unwrap();
+        w.push_str("a");
+        w.start_row();
+        w.push_null();
+        assert_eq!(w.finish(), b"x\xFF\xFD\xFE\xFFa\xFF\xFD\xFE\xFF\xFD");
+    }
+
+    #[test]
+    #[should_panic(expected = "must start a row before pushing a value")]
+    fn push_without_row() {
+        RsvWriter::new().push_str("a");
+    }
+
+    #[test]
+    fn read_row_into() {
+        let data = b"a\xFFb\xFF\xFD\xC3\xFF\xFD\xFD\xFE\xFFc";
+        let mut reader = RsvReader::new(data);
+        let mut buf = Vec::with_capacity(4);
+        let capacity = buf.capacity();
+        assert!(reader.read_row_into(&mut buf).unwrap());
+        assert_eq!(buf, vec![Some("a"), Some("b")]);
+        assert!(matches!(
+            reader.read_row_into(&mut buf),
+            Err(Error::BadUTF8 {
+                offset: 5,
+                row: 1,
+                ..
+            })
+        ));
+        assert!(buf.is_empty());
+        assert!(reader.read_row_into(&mut buf).unwrap());
+        assert!(buf.is_empty());
+        assert!(matches!(
+            reader.read_row_into(&mut buf),
+            Err(Error::UnterminatedRow { offset: 9, row: 3 })
+        ));
+        assert!(!reader.read_row_into(&mut buf).unwrap());
+        assert_eq!(buf.capacity(), capacity);
+        assert_eq!(reader.rows().count(), 4);
+    }
 }
@@ -0,0 +1,150 @@
+use crate::{Error, RsvReader, RsvRow};
+use alloc::borrow::Cow;
+use alloc::{string::String, vec::Vec};
+
+/// An RSV document decoded with `decode_rsv_lossy`.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct LossyDocument<'a> {
+    /// The decoded rows. Values are borrowed from the input unless they had to be repaired.
+    pub rows: Vec<Vec<Option<Cow<'a, str>>>>,
+    /// The values which contained invalid UTF-8, in document order.
+    pub repairs: Vec<Repair>,
+}
+
+/// The location of a value which contained invalid UTF-8, and was repaired by replacing the
+/// invalid sequences with U+FFFD.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Repair {
+    /// The byte offset of the first invalid byte.
+    pub offset: usize,
+    /// The index of the row containing the value.
+    pub row: usize,
+    /// The index of the value within its row.
+    pub value: usize,
+}
+
+impl<'a> RsvRow<'a> {
+    /// Iterates over the values in the RSV row, replacing any invalid UTF-8 with U+FFFD in the same
+    /// way as `String::from_utf8_lossy`.
+    ///
+    /// Valid values are borrowed from the input, so a value is `Cow::Owned` only if it was repaired.
+    ///
+    /// # Example:
+    /// ```
+    /// let row = librsv::RsvRow::new(b"caf\xE9\xFFok\xFF");
+    /// let values = row.values_lossy().collect::<Result<Vec<_>, _>>()?;
+    ///
+    /// assert_eq!(values, vec![Some("caf\u{FFFD}".into()), Some("ok".into())]);
+    /// # Ok::<(), librsv::Error>(())
+    /// ```
+    pub fn values_lossy(&self) -> impl Iterator<Item = Result<Option<Cow<'a, str>>, Error>> {
+        self.values_bytes()
+            .map(|value| Ok(value?.map(String::from_utf8_lossy)))
+    }
+}
+
+/// Decodes an RSV document, replacing any invalid UTF-8 with U+FFFD rather than failing.
+///
+/// The location of every repaired value is reported in `LossyDocument::repairs`. Documents with
+/// missing terminators are still rejected, as their structure cannot be recovered.
+///
+/// # Example:
+/// ```
+/// use librsv::{decode_rsv_lossy, Repair};
+///
+/// let document = decode_rsv_lossy(b"a\xFF\xFDb\xFFc\xC3\xFF\xFD")?;
+///
+/// assert_eq!(document.rows[1][1].as_deref(), Some("c\u{FFFD}"));
+/// assert_eq!(document.repairs, vec![Repair { offset: 6, row: 1, value: 1 }]);
+/// # Ok::<(), librsv::Error>(())
+/// ```
+pub fn decode_rsv_lossy(data: &[u8]) -> Result<LossyDocument<'_>, Error> {
+    let mut document = LossyDocument::default();
+    for row in RsvReader::new(data).rows() {
+        let row = row?;
+        let mut values = Vec::new();
+        for value in row.raw_values() {
+            let (offset, index, value) = value?;
+            let value = value.map(|bytes| {
+                if let Err(err) = core::str::from_utf8(bytes) {
+                    document.repairs.push(Repair {
+                        offset: offset + err.valid_up_to(),
+                        row: row.index(),
+                        value: index,
+                    });
+                }
+                String::from_utf8_lossy(bytes)
+            });
+            values.push(value);
+        }
+        document.rows.push(values);
+    }
+    Ok(document)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{decode_rsv_borrowed, encode_rsv};
+    use alloc::vec;
+
+    #[test]
+    fn matches_strict_decoding_for_valid_input() {
+        let data = encode_rsv(vec![
+            vec![Some("Hello"), None, Some("wörld")],
+            vec![],
+            vec![Some("")],
+        ]);
+        let document = decode_rsv_lossy(&data).unwrap();
+        assert!(document.repairs.is_empty());
+        assert!(document
+            .rows
+            .iter()
+            .flatten()
+            .flatten()
+            .all(|value| matches!(value, Cow::Borrowed(_))));
+        let rows = document
+            .rows
+            .iter()
+            .map(|row| row.iter().map(|value| value.as_deref()).collect::<Vec<_>>())
+            .collect::<Vec<_>>();
+        assert_eq!(rows, decode_rsv_borrowed(&data).unwrap());
+    }
+
+    #[test]
+    fn repairs_invalid_values() {
+        let data = b"\xC3(\xFFok\xFF\xFE\xFF\xFD\xFDx\xFFy\xE2\x82\xFF\xFD";
+        let document = decode_rsv_lossy(data).unwrap();
+        assert_eq!(
+            document.rows,
+            vec![
+                vec![Some("\u{FFFD}(".into()), Some("ok".into()), None],
+                vec![],
+                vec![Some("x".into()), Some("y\u{FFFD}".into())],
+            ]
+        );
+        assert_eq!(
+            document.repairs,
+            vec![
+                Repair {
+                    offset: 0,
+                    row: 0,
+                    value: 0
+                },
+                Repair {
+                    offset: 13,
+                    row: 2,
+                    value: 1
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn rejects_missing_terminators() {
+        assert!(matches!(
+            decode_rsv_lossy(b"a\xFF\xFDb"),
+            Err(Error::UnterminatedRow { offset: 3, row: 1 })
+        ));
+    }
+}
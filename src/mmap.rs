@@ -0,0 +1,104 @@
+use crate::RsvReader;
+use memmap2::Mmap;
+use std::fs::File;
+use std::io;
+use std::path::Path;
+
+/// An RSV file mapped into memory, so that it can be read without copying it into a buffer.
+///
+/// The operating system pages the file in as it is accessed, so files far larger than the
+/// available memory can be read. Rows and values are borrowed from the mapping, so the
+/// borrow checker ensures none of them outlive it.
+///
+/// # Example:
+/// ```no_run
+/// use librsv::RsvMmap;
+///
+/// let file = RsvMmap::open("data.rsv")?;
+/// for row in file.reader().rows() {
+///     for value in row?.values() {
+///         println!("{:?}", value?);
+///     }
+/// }
+/// # Ok::<(), Box<dyn std::error::Error>>(())
+/// ```
+///
+/// Values cannot be used after the mapping is dropped:
+/// ```compile_fail
+/// let value = {
+///     let file = librsv::RsvMmap::open("data.rsv").unwrap();
+///     let data = librsv::decode_rsv_borrowed(file.as_bytes()).unwrap();
+///     data[0][0]
+/// };
+/// ```
+pub struct RsvMmap {
+    mmap: Mmap,
+}
+
+impl RsvMmap {
+    /// Maps the file at the given path into memory.
+    ///
+    /// The file must not be modified or truncated while it is mapped, by this or any other process.
+    /// On most platforms, doing so will cause the values read from it to change, or a crash.
+    pub fn open<P: AsRef<Path>>(path: P) -> io::Result<Self> {
+        Self::from_file(&File::open(path)?)
+    }
+
+    /// Maps an open file into memory.
+    ///
+    /// The same restrictions as for `open` apply.
+    pub fn from_file(file: &File) -> io::Result<Self> {
+        // SAFETY: The mapping is only ever read through shared references. Undefined behaviour is
+        // still possible if the file is modified externally, which is documented above.
+        let mmap = unsafe { Mmap::map(file)? };
+        Ok(Self { mmap })
+    }
+
+    /// The contents of the file.
+    pub fn as_bytes(&self) -> &[u8] {
+        &self.mmap
+    }
+
+    /// Creates a reader over the contents of the file.
+    pub fn reader(&self) -> RsvReader<'_> {
+        RsvReader::new(&self.mmap)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{decode_rsv_borrowed, encode_rsv};
+
+    fn temp_file(name: &str, data: &[u8]) -> std::path::PathBuf {
+        let path = std::env::temp_dir().join(format!("librsv-{}-{}", std::process::id(), name));
+        std::fs::write(&path, data).unwrap();
+        path
+    }
+
+    #[test]
+    fn reads_mapped_file() {
+        let data = encode_rsv(vec![vec![Some("Hello"), None], vec![Some("wörld")]]);
+        let path = temp_file("mapped.rsv", &data);
+        let file = RsvMmap::open(&path).unwrap();
+        assert_eq!(file.as_bytes(), &data[..]);
+        let rows = file
+            .reader()
+            .rows()
+            .map(|row| row.unwrap().values().collect::<Result<Vec<_>, _>>())
+            .collect::<Result<Vec<_>, _>>()
+            .unwrap();
+        assert_eq!(rows, decode_rsv_borrowed(&data).unwrap());
+        drop(file);
+        std::fs::remove_file(path).unwrap();
+    }
+
+    #[test]
+    fn empty_file() {
+        let path = temp_file("empty.rsv", b"");
+        let file = RsvMmap::open(&path).unwrap();
+        assert_eq!(file.reader().rows().count(), 0);
+        drop(file);
+        std::fs::remove_file(path).unwrap();
+    }
+}
@@ -0,0 +1,379 @@
+use crate::{Error, RsvReader, RsvRow, RsvWriter};
+use alloc::{string::String, vec::Vec};
+use core::fmt;
+use core::iter::FromIterator;
+
+/// An owned RSV row, which stores all of its values in a single buffer.
+///
+/// Unlike `Vec<Option<String>>`, reading a row into an `OwnedRow` allocates at most three times,
+/// regardless of the number of values.
+///
+/// # Example:
+/// ```
+/// use librsv::{OwnedRow, RsvRow};
+///
+/// let row = OwnedRow::from_row(&RsvRow::new(b"Hello\xFF\xFE\xFF"))?;
+///
+/// assert_eq!(row.len(), 2);
+/// assert_eq!(row.get(0), Some(Some("Hello")));
+/// assert_eq!(row.iter().collect::<Vec<_>>(), vec![Some("Hello"), None]);
+/// assert_eq!(row, [Some("Hello"), None].iter().copied().collect());
+/// # Ok::<(), librsv::Error>(())
+/// ```
+#[derive(Clone, Default, PartialEq, Eq, Hash)]
+pub struct OwnedRow {
+    buffer: String,
+    /// The end of each value within the buffer, where each value starts at the end of the previous one.
+    ends: Vec<usize>,
+    /// Whether each value is null, in which case it is also empty.
+    nulls: Vec<bool>,
+}
+
+impl OwnedRow {
+    /// Creates a new, empty row.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Copies the values of a borrowed row, checking that they are valid UTF-8.
+    pub fn from_row(row: &RsvRow<'_>) -> Result<Self, Error> {
+        let mut owned = Self::new();
+        for value in row.values() {
+            owned.push(value?);
+        }
+        Ok(owned)
+    }
+
+    /// The number of values in the row.
+    pub fn len(&self) -> usize {
+        self.ends.len()
+    }
+
+    /// Whether the row has no values.
+    pub fn is_empty(&self) -> bool {
+        self.ends.is_empty()
+    }
+
+    /// Returns the value at the given index, or `None` if it is past the end of the row.
+    pub fn get(&self, index: usize) -> Option<Option<&str>> {
+        self.as_row().get(index)
+    }
+
+    /// Iterates over the values in the row.
+    pub fn iter(&self) -> impl Iterator<Item = Option<&str>> + '_ {
+        self.as_row().iter()
+    }
+
+    /// Appends a value to the row.
+    pub fn push(&mut self, value: Option<&str>) {
+        self.buffer.push_str(value.unwrap_or_default());
+        self.ends.push(self.buffer.len());
+        self.nulls.push(value.is_none());
+    }
+
+    /// Removes all values, keeping the allocated capacity.
+    pub fn clear(&mut self) {
+        self.buffer.clear();
+        self.ends.clear();
+        self.nulls.clear();
+    }
+
+    /// Borrows the row as an `RsvDocumentRow`.
+    pub fn as_row(&self) -> RsvDocumentRow<'_> {
+        RsvDocumentRow {
+            buffer: &self.buffer,
+            start: 0,
+            ends: &self.ends,
+            nulls: &self.nulls,
+        }
+    }
+}
+
+impl fmt::Debug for OwnedRow {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        self.as_row().fmt(f)
+    }
+}
+
+impl<'v> FromIterator<Option<&'v str>> for OwnedRow {
+    fn from_iter<I: IntoIterator<Item = Option<&'v str>>>(iter: I) -> Self {
+        let mut row = Self::new();
+        for value in iter {
+            row.push(value);
+        }
+        row
+    }
+}
+
+impl<'v> Extend<Option<&'v str>> for OwnedRow {
+    fn extend<I: IntoIterator<Item = Option<&'v str>>>(&mut self, iter: I) {
+        for value in iter {
+            self.push(value);
+        }
+    }
+}
+
+/// An owned RSV document, which stores all of its values in a single buffer.
+///
+/// This is a compact alternative to `Vec<Vec<Option<String>>>`, which makes a separate allocation
+/// for every value and row.
+///
+/// # Example:
+/// ```
+/// use librsv::RsvDocument;
+///
+/// let mut document = RsvDocument::decode(b"Hello\xFFworld\xFF\xFD")?;
+/// document.push_row(vec![None, Some("asdf")]);
+///
+/// assert_eq!(document.len(), 2);
+/// assert_eq!(document.get(1).unwrap().get(1), Some(Some("asdf")));
+/// assert_eq!(&document.encode(), b"Hello\xFFworld\xFF\xFD\xFE\xFFasdf\xFF\xFD");
+/// # Ok::<(), librsv::Error>(())
+/// ```
+#[derive(Clone, Default, PartialEq, Eq, Hash)]
+pub struct RsvDocument {
+    values: OwnedRow,
+    /// The end of each row within the values, where each row starts at the end of the previous one.
+    rows: Vec<usize>,
+}
+
+impl RsvDocument {
+    /// Creates a new, empty document.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Decodes an encoded RSV document.
+    pub fn decode(data: &[u8]) -> Result<Self, Error> {
+        let mut document = Self::new();
+        for row in RsvReader::new(data).rows() {
+            for value in row?.values() {
+                document.values.push(value?);
+            }
+            document.rows.push(document.values.len());
+        }
+        Ok(document)
+    }
+
+    /// Encodes the document.
+    pub fn encode(&self) -> Vec<u8> {
+        let mut writer = RsvWriter::with_capacity(self.values.buffer.len() + 2 * self.values.len());
+        for row in self.iter() {
+            writer.start_row();
+            for value in row.iter() {
+                writer.push(value);
+            }
+        }
+        writer.finish()
+    }
+
+    /// The number of rows in the document.
+    pub fn len(&self) -> usize {
+        self.rows.len()
+    }
+
+    /// Whether the document has no rows.
+    pub fn is_empty(&self) -> bool {
+        self.rows.is_empty()
+    }
+
+    /// Returns the row at the given index, or `None` if it is past the end of the document.
+    pub fn get(&self, index: usize) -> Option<RsvDocumentRow<'_>> {
+        let end = *self.rows.get(index)?;
+        let start = match index {
+            0 => 0,
+            _ => self.rows[index - 1],
+        };
+        let values = &self.values;
+        Some(RsvDocumentRow {
+            buffer: &values.buffer,
+            start: match start {
+                0 => 0,
+                _ => values.ends[start - 1],
+            },
+            ends: &values.ends[start..end],
+            nulls: &values.nulls[start..end],
+        })
+    }
+
+    /// Iterates over the rows in the document.
+    pub fn iter(&self) -> impl Iterator<Item = RsvDocumentRow<'_>> + '_ {
+        (0..self.len()).map(move |index| self.get(index).unwrap())
+    }
+
+    /// Appends a row to the document.
+    pub fn push_row<'v>(&mut self, row: impl IntoIterator<Item = Option<&'v str>>) {
+        self.values.extend(row);
+        self.rows.push(self.values.len());
+    }
+}
+
+impl fmt::Debug for RsvDocument {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_list().entries(self.iter()).finish()
+    }
+}
+
+impl<'v, R: IntoIterator<Item = Option<&'v str>>> FromIterator<R> for RsvDocument {
+    fn from_iter<I: IntoIterator<Item = R>>(iter: I) -> Self {
+        let mut document = Self::new();
+        for row in iter {
+            document.push_row(row);
+        }
+        document
+    }
+}
+
+/// A row borrowed from an `RsvDocument` or `OwnedRow`.
+#[derive(Clone, Copy)]
+pub struct RsvDocumentRow<'a> {
+    buffer: &'a str,
+    /// The start of the first value within the buffer.
+    start: usize,
+    ends: &'a [usize],
+    nulls: &'a [bool],
+}
+
+impl<'a> RsvDocumentRow<'a> {
+    /// The number of values in the row.
+    pub fn len(&self) -> usize {
+        self.ends.len()
+    }
+
+    /// Whether the row has no values.
+    pub fn is_empty(&self) -> bool {
+        self.ends.is_empty()
+    }
+
+    /// Returns the value at the given index, or `None` if it is past the end of the row.
+    pub fn get(&self, index: usize) -> Option<Option<&'a str>> {
+        let end = *self.ends.get(index)?;
+        if self.nulls[index] {
+            return Some(None);
+        }
+        let start = match index {
+            0 => self.start,
+            _ => self.ends[index - 1],
+        };
+        Some(Some(&self.buffer[start..end]))
+    }
+
+    /// Iterates over the values in the row.
+    pub fn iter(&self) -> impl Iterator<Item = Option<&'a str>> {
+        let buffer = self.buffer;
+        let mut start = self.start;
+        self.ends.iter().zip(self.nulls).map(move |(&end, &null)| {
+            let value = &buffer[start..end];
+            start = end;
+            match null {
+                true => None,
+                false => Some(value),
+            }
+        })
+    }
+
+    /// Copies the row into an `OwnedRow`.
+    pub fn to_owned_row(&self) -> OwnedRow {
+        self.iter().collect()
+    }
+}
+
+impl PartialEq for RsvDocumentRow<'_> {
+    fn eq(&self, other: &Self) -> bool {
+        self.iter().eq(other.iter())
+    }
+}
+
+impl Eq for RsvDocumentRow<'_> {}
+
+impl fmt::Debug for RsvDocumentRow<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_list().entries(self.iter()).finish()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{decode_rsv, encode_rsv};
+    use alloc::{format, vec};
+
+    #[test]
+    fn stores_values_contiguously() {
+        let mut row = OwnedRow::new();
+        row.push(Some("a"));
+        row.push(None);
+        row.push(Some(""));
+        row.push(Some("bc"));
+        assert_eq!(row.buffer, "abc");
+        assert_eq!(row.len(), 4);
+        assert_eq!(
+            (0..5).map(|i| row.get(i)).collect::<Vec<_>>(),
+            vec![
+                Some(Some("a")),
+                Some(None),
+                Some(Some("")),
+                Some(Some("bc")),
+                None
+            ]
+        );
+        assert_eq!(
+            row.iter().collect::<Vec<_>>(),
+            vec![Some("a"), None, Some(""), Some("bc")]
+        );
+        assert_eq!(
+            format!("{:?}", row),
+            r#"[Some("a"), None, Some(""), Some("bc")]"#
+        );
+    }
+
+    #[test]
+    fn document_roundtrip() {
+        let data = encode_rsv(vec![
+            vec![Some("Hello"), None, Some("wörld")],
+            vec![],
+            vec![Some(""), Some("x")],
+        ]);
+        let document = RsvDocument::decode(&data).unwrap();
+        assert_eq!(document.encode(), data);
+
+        let rows = document
+            .iter()
+            .map(|row| row.iter().map(|v| v.map(String::from)).collect::<Vec<_>>())
+            .collect::<Vec<_>>();
+        assert_eq!(rows, decode_rsv(&data).unwrap());
+        assert_eq!(document.get(2).unwrap().get(1), Some(Some("x")));
+        assert!(document.get(1).unwrap().is_empty());
+        assert!(document.get(3).is_none());
+
+        assert!(matches!(
+            RsvDocument::decode(b"a\xFF\xFD\xC3\xFF\xFD"),
+            Err(Error::BadUTF8 { row: 1, .. })
+        ));
+    }
+
+    #[test]
+    fn equality() {
+        let mut a = RsvDocument::new();
+        a.push_row(vec![Some("ab"), None]);
+        a.push_row(vec![]);
+        let b = vec![vec![Some("ab"), None], vec![]]
+            .into_iter()
+            .collect::<RsvDocument>();
+        assert_eq!(a, b);
+
+        // The same values split differently between rows or values are not equal
+        let c = vec![vec![Some("a"), Some("b"), None], vec![]]
+            .into_iter()
+            .collect::<RsvDocument>();
+        let d = vec![vec![Some("ab")], vec![None]]
+            .into_iter()
+            .collect::<RsvDocument>();
+        assert_ne!(a, c);
+        assert_ne!(a, d);
+
+        let row = a.get(0).unwrap();
+        assert_eq!(row.to_owned_row().as_row(), row);
+        assert_ne!(a.get(0), a.get(1));
+    }
+}
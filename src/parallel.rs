@@ -0,0 +1,136 @@
+use crate::{decode_rows, rows_at, scan, Error, END_ROW};
+use rayon::prelude::*;
+
+/// The smallest chunk that is worth decoding on its own thread.
+const MIN_CHUNK_SIZE: usize = 64 * 1024;
+
+/// Splits an RSV document into chunks of at least `chunk_size` bytes (except for the last chunk),
+/// each ending on a row boundary.
+///
+/// Because the row terminator byte can never appear within a value, each chunk can be decoded
+/// independently of the others. If the document is not properly terminated, the last chunk
+/// will contain the unterminated row.
+///
+/// # Example:
+/// ```
+/// let buffer = b"a\xFF\xFDb\xFF\xFDc\xFF\xFD";
+/// let chunks = librsv::split_rows(buffer, 4);
+///
+/// assert_eq!(chunks, vec![&b"a\xFF\xFDb\xFF\xFD"[..], &b"c\xFF\xFD"[..]]);
+/// ```
+pub fn split_rows(data: &[u8], chunk_size: usize) -> Vec<&[u8]> {
+    let mut chunks = vec![];
+    let mut remain = data;
+    while !remain.is_empty() {
+        let search_from = chunk_size.clamp(1, remain.len()) - 1;
+        let end = match scan::find(END_ROW, &remain[search_from..]) {
+            Some(i) => search_from + i + 1,
+            None => remain.len(),
+        };
+        let (chunk, rest) = remain.split_at(end);
+        chunks.push(chunk);
+        remain = rest;
+    }
+    chunks
+}
+
+/// Decodes an RSV document into a `Vec<Vec<Option<String>>>`, using multiple threads.
+///
+/// The result is identical to that of `decode_rsv`, including the position reported by any error,
+/// but large documents are decoded in row-aligned chunks on the `rayon` thread pool.
+///
+/// # Example:
+/// ```
+/// let buffer = b"Hello\xFFworld\xFF\xFD";
+/// let data = librsv::decode_rsv_parallel(buffer).unwrap();
+///
+/// assert_eq!(data, vec![vec![Some("Hello".into()), Some("world".into())]]);
+/// ```
+pub fn decode_rsv_parallel(data: &[u8]) -> Result<Vec<Vec<Option<String>>>, Error> {
+    // Use a few chunks per thread so that uneven chunks are balanced out
+    let chunk_size = (data.len() / (4 * rayon::current_num_threads())).max(MIN_CHUNK_SIZE);
+    let chunks = split_rows(data, chunk_size);
+
+    let mut offset = 0;
+    let chunks = chunks
+        .into_iter()
+        .map(|chunk| {
+            let start = offset;
+            offset += chunk.len();
+            (chunk, start)
+        })
+        .collect::<Vec<_>>();
+
+    // Each chunk numbers its rows from zero, so errors must be shifted by the preceding rows
+    let decoded = chunks
+        .into_par_iter()
+        .map(|(chunk, offset)| decode_rows(rows_at(chunk, offset, 0)))
+        .collect::<Vec<_>>();
+
+    let mut rows = Vec::new();
+    for chunk in decoded {
+        match chunk {
+            Ok(chunk) => rows.extend(chunk),
+            Err(err) => return Err(err.offset_rows(rows.len())),
+        }
+    }
+    Ok(rows)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{decode_rsv, encode_rsv};
+
+    fn document(rows: usize) -> Vec<u8> {
+        let data = (0..rows)
+            .map(|i| vec![Some(i.to_string()), None, Some("x".repeat(i % 50))])
+            .collect::<Vec<_>>();
+        encode_rsv(data)
+    }
+
+    #[test]
+    fn chunks_end_on_row_boundaries() {
+        let buffer = document(1000);
+        for chunk_size in [0, 1, 7, 100, 5000, buffer.len(), buffer.len() + 1] {
+            let chunks = split_rows(&buffer, chunk_size);
+            assert_eq!(chunks.concat(), buffer);
+            assert!(chunks.iter().all(|c| c.last() == Some(&END_ROW)));
+        }
+        assert!(split_rows(&[], 10).is_empty());
+    }
+
+    #[test]
+    fn matches_decode_rsv() {
+        let buffer = document(20_000);
+        assert!(buffer.len() > 2 * MIN_CHUNK_SIZE);
+        assert_eq!(
+            decode_rsv_parallel(&buffer).unwrap(),
+            decode_rsv(&buffer).unwrap()
+        );
+    }
+
+    #[test]
+    fn reports_global_error_positions() {
+        let mut buffer = document(20_000);
+        let bad_row = 15_000;
+        let offset = split_rows(&buffer, 1)[..bad_row]
+            .iter()
+            .map(|c| c.len())
+            .sum::<usize>();
+        buffer[offset] = 0xC3;
+
+        let expected = decode_rsv(&buffer).unwrap_err();
+        let actual = decode_rsv_parallel(&buffer).unwrap_err();
+        assert!(matches!(expected, Error::BadUTF8 { row, .. } if row == bad_row));
+        assert_eq!(actual.to_string(), expected.to_string());
+
+        let mut buffer = document(20_000);
+        buffer.push(b'a');
+        let actual = decode_rsv_parallel(&buffer).unwrap_err();
+        assert_eq!(
+            actual.to_string(),
+            decode_rsv(&buffer).unwrap_err().to_string()
+        );
+    }
+}
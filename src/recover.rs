@@ -0,0 +1,193 @@
+use crate::{scan, Error, RsvReader, RsvRow, END_ROW};
+use alloc::vec::Vec;
+use core::ops::Range;
+
+/// A problem found by `RsvReader::rows_recovering`, which caused a row to be dropped, or was found
+/// in an unterminated final row.
+#[derive(Debug)]
+pub struct Diagnostic {
+    /// The byte range of the affected row, including its terminator if it has one.
+    pub range: Range<usize>,
+    /// The error found within the row.
+    pub error: Error,
+}
+
+/// An iterator over the valid rows of a possibly corrupted RSV document.
+///
+/// Created by `RsvReader::rows_recovering`.
+pub struct RecoveringRows<'a> {
+    remain: &'a [u8],
+    offset: usize,
+    index: usize,
+    diagnostics: Vec<Diagnostic>,
+}
+
+impl<'a> RsvReader<'a> {
+    /// Iterates over the rows in the RSV document, skipping any which are malformed rather than failing.
+    ///
+    /// Because the row terminator byte can never appear within a value, reading resumes at the next
+    /// row after a malformed one. Every value of each yielded row is known to be valid, and rows keep
+    /// their index within the whole document.
+    ///
+    /// A row containing an unterminated value or invalid UTF-8 is dropped in its entirety, including
+    /// any valid values before the bad one, so that no row is yielded with values missing. If the
+    /// document ends without a row terminator, but the last row is otherwise valid, it is still
+    /// yielded. Either way, a `Diagnostic` is recorded, which can be retrieved with
+    /// `RecoveringRows::diagnostics` once iteration is complete.
+    ///
+    /// # Example:
+    /// ```
+    /// use librsv::{Error, RsvReader};
+    ///
+    /// let buffer = b"a\xFF\xFDb\xFFc\xC3\xFF\xFDd\xFF\xFD";
+    /// let mut rows = RsvReader::new(buffer).rows_recovering();
+    ///
+    /// let values = rows.by_ref().map(|row| row.index()).collect::<Vec<_>>();
+    /// assert_eq!(values, vec![0, 2]);
+    ///
+    /// let diagnostics = rows.into_diagnostics();
+    /// assert_eq!(diagnostics[0].range, 3..9);
+    /// assert!(matches!(diagnostics[0].error, Error::BadUTF8 { row: 1, .. }));
+    /// ```
+    pub fn rows_recovering(&self) -> RecoveringRows<'a> {
+        RecoveringRows {
+            remain: self.data,
+            offset: 0,
+            index: 0,
+            diagnostics: Vec::new(),
+        }
+    }
+}
+
+impl<'a> RecoveringRows<'a> {
+    /// The problems found so far, in document order.
+    pub fn diagnostics(&self) -> &[Diagnostic] {
+        &self.diagnostics
+    }
+
+    /// Consumes the iterator, returning the problems found.
+    pub fn into_diagnostics(self) -> Vec<Diagnostic> {
+        self.diagnostics
+    }
+}
+
+impl<'a> Iterator for RecoveringRows<'a> {
+    type Item = RsvRow<'a>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        while !self.remain.is_empty() {
+            let (data, len, terminated) = match scan::find(END_ROW, self.remain) {
+                Some(terminator) => (&self.remain[..terminator], terminator + 1, true),
+                None => (self.remain, self.remain.len(), false),
+            };
+            let row = RsvRow::at(data, self.offset, self.index);
+            let range = self.offset..self.offset + len;
+            self.remain = &self.remain[len..];
+            self.offset += len;
+            self.index += 1;
+
+            if let Err(error) = row.values().try_for_each(|value| value.map(drop)) {
+                self.diagnostics.push(Diagnostic { range, error });
+                continue;
+            }
+            if !terminated {
+                let error = Error::UnterminatedRow {
+                    offset: row.offset(),
+                    row: row.index(),
+                };
+                self.diagnostics.push(Diagnostic { range, error });
+            }
+            return Some(row);
+        }
+        None
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{decode_rsv, encode_rsv};
+    use alloc::string::String;
+    use alloc::vec;
+
+    fn decode(rows: &mut RecoveringRows) -> Vec<Vec<Option<String>>> {
+        rows.map(|row| {
+            row.values()
+                .map(|value| value.unwrap().map(String::from))
+                .collect()
+        })
+        .collect()
+    }
+
+    #[test]
+    fn valid_documents_are_unchanged() {
+        let data = encode_rsv(vec![vec![Some("a"), None], vec![], vec![Some("")]]);
+        let mut rows = RsvReader::new(&data).rows_recovering();
+        assert_eq!(decode(&mut rows), decode_rsv(&data).unwrap());
+        assert!(rows.diagnostics().is_empty());
+    }
+
+    #[test]
+    fn skips_malformed_rows() {
+        let data = b"a\xFF\xFDb\xFFc\xFDd\xFF\xFD\xC3\xFF\xFDe\xFF";
+        let mut rows = RsvReader::new(data).rows_recovering();
+        assert_eq!(
+            decode(&mut rows),
+            vec![
+                vec![Some("a".into())],
+                vec![Some("d".into())],
+                vec![Some("e".into())],
+            ]
+        );
+
+        let diagnostics = rows.into_diagnostics();
+        let ranges = diagnostics
+            .iter()
+            .map(|d| d.range.clone())
+            .collect::<Vec<_>>();
+        assert_eq!(ranges, vec![3..7, 10..13, 13..15]);
+        assert!(matches!(
+            diagnostics[0].error,
+            Error::UnterminatedValue {
+                offset: 5,
+                row: 1,
+                value: 1
+            }
+        ));
+        assert!(matches!(
+            diagnostics[1].error,
+            Error::BadUTF8 {
+                offset: 10,
+                row: 3,
+                value: 0,
+                ..
+            }
+        ));
+        assert!(matches!(
+            diagnostics[2].error,
+            Error::UnterminatedRow { offset: 13, row: 4 }
+        ));
+    }
+
+    #[test]
+    fn drops_whole_row_with_bad_value() {
+        let data = b"a\xFF\xC3\xFFb\xFF\xFDc\xFF\xFD";
+        let mut rows = RsvReader::new(data).rows_recovering();
+        let row = rows.next().unwrap();
+        assert_eq!(row.index(), 1);
+        assert!(rows.next().is_none());
+
+        let diagnostics = rows.into_diagnostics();
+        assert_eq!(diagnostics.len(), 1);
+        assert_eq!(diagnostics[0].range, 0..7);
+        assert!(matches!(
+            diagnostics[0].error,
+            Error::BadUTF8 {
+                offset: 2,
+                row: 0,
+                value: 1,
+                ..
+            }
+        ));
+    }
+}
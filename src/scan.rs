@@ -0,0 +1,98 @@
+//! Searching for terminator bytes.
+//!
+//! Scanning for row and value terminators dominates the cost of parsing, so rather than
+//! inspecting one byte at a time, the input is searched eight bytes at a time using plain
+//! integer arithmetic. With the `memchr` feature enabled, the `memchr` crate is used instead,
+//! which takes advantage of SIMD instructions where available.
+
+use core::convert::TryInto;
+
+/// Returns the index of the first occurrence of `needle` in `haystack`.
+#[inline]
+pub(crate) fn find(needle: u8, haystack: &[u8]) -> Option<usize> {
+    #[cfg(feature = "memchr")]
+    {
+        memchr::memchr(needle, haystack)
+    }
+    #[cfg(not(feature = "memchr"))]
+    {
+        find_word(needle, haystack)
+    }
+}
+
+const WORD: usize = core::mem::size_of::<u64>();
+const LO: u64 = u64::from_ne_bytes([0x01; WORD]);
+const HI: u64 = u64::from_ne_bytes([0x80; WORD]);
+
+/// Searches for `needle` one word at a time.
+///
+/// Each word is XORed with `needle` repeated in every byte, so that matching bytes become zero,
+/// and then the zero bytes are found using the classic "has zero byte" bit trick.
+#[cfg_attr(feature = "memchr", allow(dead_code))]
+fn find_word(needle: u8, haystack: &[u8]) -> Option<usize> {
+    let pattern = LO * needle as u64;
+    let mut chunks = haystack.chunks_exact(WORD);
+    for (i, chunk) in chunks.by_ref().enumerate() {
+        let word = u64::from_le_bytes(chunk.try_into().unwrap()) ^ pattern;
+        // Borrows can only cause false positives above a true zero byte,
+        // so the lowest set bit always marks the first match.
+        let zeros = word.wrapping_sub(LO) & !word & HI;
+        if zeros != 0 {
+            return Some(i * WORD + zeros.trailing_zeros() as usize / 8);
+        }
+    }
+    let offset = haystack.len() - chunks.remainder().len();
+    find_scalar(needle, chunks.remainder()).map(|i| offset + i)
+}
+
+/// Searches for `needle` one byte at a time.
+fn find_scalar(needle: u8, haystack: &[u8]) -> Option<usize> {
+    haystack.iter().position(|c| *c == needle)
+}
+
+#[cfg(all(test, feature = "alloc"))]
+mod tests {
+    use super::*;
+    use crate::{END_ROW, END_VALUE, NULL_VALUE};
+    use alloc::vec::Vec;
+    use proptest::prelude::*;
+
+    /// Generates bytes with a high proportion of terminator bytes.
+    fn haystack() -> impl Strategy<Value = Vec<u8>> {
+        let byte = prop_oneof![
+            4 => any::<u8>(),
+            1 => Just(END_ROW),
+            1 => Just(NULL_VALUE),
+            1 => Just(END_VALUE),
+        ];
+        prop::collection::vec(byte, 0..100)
+    }
+
+    proptest! {
+        #[test]
+        fn word_matches_scalar(haystack in haystack(), needle in any::<u8>()) {
+            prop_assert_eq!(find_word(needle, &haystack), find_scalar(needle, &haystack));
+        }
+
+        #[test]
+        fn word_matches_scalar_for_terminators(haystack in haystack()) {
+            for needle in [END_ROW, END_VALUE] {
+                prop_assert_eq!(find_word(needle, &haystack), find_scalar(needle, &haystack));
+            }
+        }
+
+        #[test]
+        fn find_matches_scalar(haystack in haystack(), needle in any::<u8>()) {
+            prop_assert_eq!(find(needle, &haystack), find_scalar(needle, &haystack));
+        }
+    }
+
+    #[test]
+    fn finds_first_match() {
+        let haystack = b"aaaaaaaaaaa\xFDa\xFD\xFD";
+        assert_eq!(find_word(END_ROW, haystack), Some(11));
+        assert_eq!(find_word(END_ROW, &haystack[12..]), Some(1));
+        assert_eq!(find_word(END_VALUE, haystack), None);
+        assert_eq!(find_word(0, &[0x01, 0x00]), Some(1));
+    }
+}
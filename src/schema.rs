@@ -0,0 +1,423 @@
+use crate::{rows_at, Error, RsvReader, RsvRow, RsvWriter};
+use alloc::boxed::Box;
+use alloc::{string::String, vec::Vec};
+use core::fmt;
+
+/// The layout which the rows of an RSV document must follow.
+///
+/// A schema declares each column's name, whether it may contain nulls, and any checks which its
+/// values must pass. `RsvReader::rows_with_schema` and `RsvSchemaWriter` enforce the schema when
+/// reading and writing.
+///
+/// # Example:
+/// ```
+/// use librsv::{Column, RsvReader, Schema};
+///
+/// let schema = Schema::new()
+///     .column(Column::new("name"))
+///     .column(Column::new("age").nullable(true).check("integer", |v| v.parse::<u32>().is_ok()));
+///
+/// let buffer = b"Alice\xFF30\xFF\xFDBob\xFF\xFE\xFF\xFDCarol\xFFold\xFF\xFD";
+/// let mut rows = RsvReader::new(buffer).rows_with_schema(&schema);
+///
+/// assert!(rows.next().unwrap().is_ok());
+/// assert!(rows.next().unwrap().is_ok());
+/// assert_eq!(
+///     rows.next().unwrap().err().unwrap().to_string(),
+///     "row 2, column 1 (\"age\") failed the integer check"
+/// );
+/// ```
+#[derive(Clone, Debug, Default)]
+pub struct Schema {
+    columns: Vec<Column>,
+    header_row: bool,
+}
+
+/// A named check on the values of a column.
+type Check = Box<dyn CheckFn>;
+
+/// A check which can be cloned from behind a `Box`.
+///
+/// This is used rather than `Arc`, which is not available on targets without atomic pointers.
+trait CheckFn: Fn(&str) -> bool + Send + Sync {
+    fn clone_box(&self) -> Box<dyn CheckFn>;
+}
+
+impl<F: Fn(&str) -> bool + Clone + Send + Sync + 'static> CheckFn for F {
+    fn clone_box(&self) -> Box<dyn CheckFn> {
+        Box::new(self.clone())
+    }
+}
+
+impl Clone for Box<dyn CheckFn> {
+    fn clone(&self) -> Self {
+        // The box itself also implements `CheckFn`, so the call must go through the trait object
+        (**self).clone_box()
+    }
+}
+
+/// A column declared by a `Schema`.
+#[derive(Clone)]
+pub struct Column {
+    name: String,
+    nullable: bool,
+    checks: Vec<(String, Check)>,
+}
+
+/// A rule of a `Schema` which a value broke.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum SchemaRule {
+    /// The value was null, but the column is not nullable.
+    NotNull,
+    /// The value failed the check with the given name.
+    Check(String),
+    /// The header row had a different name for the column.
+    Name {
+        /// The name found in the header row.
+        found: Option<String>,
+    },
+}
+
+impl fmt::Display for SchemaRule {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            SchemaRule::NotNull => write!(f, "is null, but the column is not nullable"),
+            SchemaRule::Check(name) => write!(f, "failed the {name} check"),
+            SchemaRule::Name { found: Some(name) } => {
+                write!(f, "is named {name:?} in the header row")
+            }
+            SchemaRule::Name { found: None } => write!(f, "is missing from the header row"),
+        }
+    }
+}
+
+impl Schema {
+    /// Creates a schema with no columns.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Adds a column.
+    pub fn column(mut self, column: Column) -> Self {
+        self.columns.push(column);
+        self
+    }
+
+    /// Sets whether documents start with a header row containing the column names.
+    ///
+    /// If so, readers check the header row against the column names rather than the other rules,
+    /// and `RsvSchemaWriter` writes it automatically. Defaults to `false`.
+    pub fn header_row(mut self, header_row: bool) -> Self {
+        self.header_row = header_row;
+        self
+    }
+
+    /// The declared columns.
+    pub fn columns(&self) -> &[Column] {
+        &self.columns
+    }
+
+    /// Checks that the values of a row follow the schema.
+    ///
+    /// The row index and byte offset are only used to report errors.
+    pub fn check(&self, row: usize, offset: usize, values: &[Option<&str>]) -> Result<(), Error> {
+        if values.len() != self.columns.len() {
+            return Err(Error::RowWidthMismatch {
+                offset,
+                row,
+                expected: self.columns.len(),
+                found: values.len(),
+            });
+        }
+        let violation = |column: usize, rule| Error::SchemaViolation {
+            row,
+            column,
+            name: self.columns[column].name.clone(),
+            rule,
+        };
+        for (index, (column, value)) in self.columns.iter().zip(values).enumerate() {
+            let Some(value) = value else {
+                if !column.nullable {
+                    return Err(violation(index, SchemaRule::NotNull));
+                }
+                continue;
+            };
+            if let Some((name, _)) = column.checks.iter().find(|(_, check)| !check(value)) {
+                return Err(violation(index, SchemaRule::Check(name.clone())));
+            }
+        }
+        Ok(())
+    }
+
+    /// Checks that a header row contains the column names, in order.
+    pub fn check_header(&self, row: &RsvRow<'_>) -> Result<(), Error> {
+        let values = row.values().collect::<Result<Vec<_>, _>>()?;
+        for (index, column) in self.columns.iter().enumerate() {
+            let found = values.get(index).copied().flatten();
+            if found != Some(column.name.as_str()) {
+                return Err(Error::SchemaViolation {
+                    row: row.index(),
+                    column: index,
+                    name: column.name.clone(),
+                    rule: SchemaRule::Name {
+                        found: found.map(String::from),
+                    },
+                });
+            }
+        }
+        if values.len() != self.columns.len() {
+            return Err(Error::RowWidthMismatch {
+                offset: row.offset(),
+                row: row.index(),
+                expected: self.columns.len(),
+                found: values.len(),
+            });
+        }
+        Ok(())
+    }
+}
+
+impl Column {
+    /// Creates a non-nullable column with the given name and no checks.
+    pub fn new(name: impl Into<String>) -> Self {
+        Self {
+            name: name.into(),
+            nullable: false,
+            checks: Vec::new(),
+        }
+    }
+
+    /// Sets whether the column may contain nulls. Defaults to `false`.
+    pub fn nullable(mut self, nullable: bool) -> Self {
+        self.nullable = nullable;
+        self
+    }
+
+    /// Adds a check which every non-null value in the column must pass.
+    ///
+    /// The name describes the check in error messages. Any function of a `&str` can be used,
+    /// such as a closure which matches the value against a regular expression. It must be `Clone`,
+    /// as closures are when everything they capture is, so that the column can be cloned.
+    pub fn check<F>(mut self, name: impl Into<String>, check: F) -> Self
+    where
+        F: Fn(&str) -> bool + Clone + Send + Sync + 'static,
+    {
+        self.checks.push((name.into(), Box::new(check)));
+        self
+    }
+
+    /// The name of the column.
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    /// Whether the column may contain nulls.
+    pub fn is_nullable(&self) -> bool {
+        self.nullable
+    }
+}
+
+impl fmt::Debug for Column {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let checks = self.checks.iter().map(|(name, _)| name).collect::<Vec<_>>();
+        f.debug_struct("Column")
+            .field("name", &self.name)
+            .field("nullable", &self.nullable)
+            .field("checks", &checks)
+            .finish()
+    }
+}
+
+impl<'a> RsvReader<'a> {
+    /// Iterates over the rows in the RSV document, checking that each follows the schema.
+    ///
+    /// If the schema declares a header row, it is checked against the column names and not yielded.
+    pub fn rows_with_schema<'s>(
+        &self,
+        schema: &'s Schema,
+    ) -> impl Iterator<Item = Result<RsvRow<'a>, Error>> + 's
+    where
+        'a: 's,
+    {
+        let mut values = Vec::new();
+        let mut header_row = schema.header_row;
+        rows_at(self.data, 0, 0).filter_map(move |row| {
+            let result = row.and_then(|row| {
+                if core::mem::take(&mut header_row) {
+                    return schema.check_header(&row).map(|_| None);
+                }
+                values.clear();
+                for value in row.values() {
+                    values.push(value?);
+                }
+                schema.check(row.index(), row.offset(), &values)?;
+                Ok(Some(row))
+            });
+            result.transpose()
+        })
+    }
+}
+
+/// Writes an RSV document, checking that each row follows a `Schema` before it is written.
+///
+/// # Example:
+/// ```
+/// use librsv::{Column, RsvSchemaWriter, Schema};
+///
+/// let schema = Schema::new().column(Column::new("id")).header_row(true);
+/// let mut writer = RsvSchemaWriter::new(&schema);
+/// writer.write_row(&[Some("1")])?;
+/// assert!(writer.write_row(&[None]).is_err());
+///
+/// assert_eq!(&writer.finish(), b"id\xFF\xFD1\xFF\xFD");
+/// # Ok::<(), librsv::Error>(())
+/// ```
+pub struct RsvSchemaWriter<'s> {
+    writer: RsvWriter,
+    schema: &'s Schema,
+    offset: usize,
+    rows: usize,
+}
+
+impl<'s> RsvSchemaWriter<'s> {
+    /// Creates a new `RsvSchemaWriter`, writing the header row if the schema declares one.
+    pub fn new(schema: &'s Schema) -> Self {
+        let mut writer = Self {
+            writer: RsvWriter::new(),
+            schema,
+            offset: 0,
+            rows: 0,
+        };
+        if schema.header_row {
+            let names = schema
+                .columns
+                .iter()
+                .map(|column| Some(column.name.as_str()));
+            writer.push_row(names);
+        }
+        writer
+    }
+
+    /// Writes a row, if it follows the schema. Otherwise, nothing is written.
+    pub fn write_row(&mut self, values: &[Option<&str>]) -> Result<(), Error> {
+        self.schema.check(self.rows, self.offset, values)?;
+        self.push_row(values.iter().copied());
+        Ok(())
+    }
+
+    fn push_row<'v>(&mut self, values: impl Iterator<Item = Option<&'v str>>) {
+        self.writer.start_row();
+        for value in values {
+            self.offset += value.map_or(1, str::len) + 1;
+            self.writer.push(value);
+        }
+        self.offset += 1;
+        self.rows += 1;
+    }
+
+    /// Finishes writing and returns the encoded document.
+    pub fn finish(self) -> Vec<u8> {
+        self.writer.finish()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use alloc::string::ToString;
+
+    fn schema() -> Schema {
+        Schema::new()
+            .column(Column::new("id").check("integer", |v| v.parse::<i64>().is_ok()))
+            .column(
+                Column::new("note")
+                    .nullable(true)
+                    .check("short", |v| v.len() < 5),
+            )
+    }
+
+    #[test]
+    fn reader_enforces_schema() {
+        // A clone runs the same checks, even once the original is dropped
+        let original = schema();
+        let schema = original.clone();
+        drop(original);
+        let check = |data: &[u8]| {
+            RsvReader::new(data)
+                .rows_with_schema(&schema)
+                .collect::<Result<Vec<_>, _>>()
+                .map(|rows| rows.len())
+        };
+        assert_eq!(check(b"1\xFFa\xFF\xFD2\xFF\xFE\xFF\xFD").unwrap(), 2);
+        assert!(matches!(
+            check(b"1\xFFa\xFF\xFD\xFE\xFFb\xFF\xFD"),
+            Err(Error::SchemaViolation {
+                row: 1,
+                column: 0,
+                rule: SchemaRule::NotNull,
+                ..
+            })
+        ));
+        assert!(matches!(
+            check(b"x\xFFa\xFF\xFD"),
+            Err(Error::SchemaViolation { row: 0, column: 0, rule: SchemaRule::Check(name), .. }) if name == "integer"
+        ));
+        assert!(matches!(
+            check(b"1\xFFtoo long\xFF\xFD"),
+            Err(Error::SchemaViolation { column: 1, rule: SchemaRule::Check(name), .. }) if name == "short"
+        ));
+        assert!(matches!(
+            check(b"1\xFFa\xFF\xFD1\xFF\xFD"),
+            Err(Error::RowWidthMismatch {
+                offset: 5,
+                row: 1,
+                expected: 2,
+                found: 1
+            })
+        ));
+    }
+
+    #[test]
+    fn checks_header_row() {
+        let schema = schema().header_row(true);
+        let rows = |data: &[u8]| {
+            RsvReader::new(data)
+                .rows_with_schema(&schema)
+                .collect::<Result<Vec<_>, _>>()
+                .map(|rows| rows.len())
+        };
+        assert_eq!(rows(b"id\xFFnote\xFF\xFD1\xFF\xFE\xFF\xFD").unwrap(), 1);
+        let err = rows(b"id\xFFnotes\xFF\xFD").unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "row 0, column 1 (\"note\") is named \"notes\" in the header row"
+        );
+        assert!(rows(b"id\xFF\xFD").is_err());
+        assert!(rows(b"id\xFFnote\xFFextra\xFF\xFD").is_err());
+    }
+
+    #[test]
+    fn writer_enforces_schema() {
+        let schema = schema().header_row(true);
+        let mut writer = RsvSchemaWriter::new(&schema);
+        writer.write_row(&[Some("1"), None]).unwrap();
+        assert!(matches!(
+            writer.write_row(&[Some("2")]),
+            Err(Error::RowWidthMismatch {
+                offset: 14,
+                row: 2,
+                ..
+            })
+        ));
+        assert!(writer.write_row(&[None, None]).is_err());
+        writer.write_row(&[Some("3"), Some("abc")]).unwrap();
+
+        let buffer = writer.finish();
+        assert_eq!(
+            &buffer,
+            b"id\xFFnote\xFF\xFD1\xFF\xFE\xFF\xFD3\xFFabc\xFF\xFD"
+        );
+        let rows = RsvReader::new(&buffer).rows_with_schema(&schema).count();
+        assert_eq!(rows, 2);
+    }
+}
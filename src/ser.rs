@@ -0,0 +1,531 @@
+use crate::{Error, RsvStreamWriter};
+use serde::ser::{self, Impossible, Serialize};
+use std::io::Write;
+
+/// Serializes a sequence of rows as an RSV document, returning the encoded bytes.
+///
+/// Each row must serialize as a struct, tuple or sequence, whose fields are written as values in order.
+/// Strings are written as-is, numbers, bools and chars are written using their string form,
+/// and `None` or `()` is written as a null value.
+///
+/// # Example:
+/// ```
+/// #[derive(serde::Serialize)]
+/// struct Person {
+///     name: String,
+///     age: Option<u32>,
+/// }
+///
+/// let people = vec![
+///     Person { name: "Alice".into(), age: Some(30) },
+///     Person { name: "Bob".into(), age: None },
+/// ];
+/// let buffer = librsv::to_vec(&people).unwrap();
+///
+/// assert_eq!(&buffer, b"Alice\xFF30\xFF\xFDBob\xFF\xFE\xFF\xFD");
+/// ```
+pub fn to_vec<T>(value: &T) -> Result<Vec<u8>, Error>
+where
+    T: ?Sized + Serialize,
+{
+    let mut buffer = Vec::new();
+    to_writer(&mut buffer, value)?;
+    Ok(buffer)
+}
+
+/// Serializes a sequence of rows as an RSV document, writing it to the given sink.
+///
+/// See `to_vec` for details on how values are encoded.
+pub fn to_writer<W, T>(writer: W, value: &T) -> Result<(), Error>
+where
+    W: Write,
+    T: ?Sized + Serialize,
+{
+    let mut writer = RsvStreamWriter::new(writer);
+    value.serialize(DocumentSerializer {
+        writer: &mut writer,
+    })?;
+    writer.finish()?;
+    Ok(())
+}
+
+impl ser::Error for Error {
+    fn custom<T: std::fmt::Display>(msg: T) -> Self {
+        Error::Message(msg.to_string())
+    }
+}
+
+/// Serializes the document as a sequence of rows.
+struct DocumentSerializer<'a, W: Write> {
+    writer: &'a mut RsvStreamWriter<W>,
+}
+
+/// Serializes a single row as a sequence of values.
+struct RowSerializer<'a, W: Write> {
+    writer: &'a mut RsvStreamWriter<W>,
+}
+
+/// Serializes a single value.
+struct ValueSerializer<'a, W: Write> {
+    writer: &'a mut RsvStreamWriter<W>,
+}
+
+fn unsupported(what: &str) -> Error {
+    Error::Message(format!("cannot serialize {what}"))
+}
+
+/// Implements the serializer methods for scalar types, which are rejected at the document and row levels.
+macro_rules! reject_scalars {
+    ($what:expr) => {
+        fn serialize_bool(self, _: bool) -> Result<(), Error> {
+            Err(unsupported($what))
+        }
+        fn serialize_i8(self, _: i8) -> Result<(), Error> {
+            Err(unsupported($what))
+        }
+        fn serialize_i16(self, _: i16) -> Result<(), Error> {
+            Err(unsupported($what))
+        }
+        fn serialize_i32(self, _: i32) -> Result<(), Error> {
+            Err(unsupported($what))
+        }
+        fn serialize_i64(self, _: i64) -> Result<(), Error> {
+            Err(unsupported($what))
+        }
+        fn serialize_u8(self, _: u8) -> Result<(), Error> {
+            Err(unsupported($what))
+        }
+        fn serialize_u16(self, _: u16) -> Result<(), Error> {
+            Err(unsupported($what))
+        }
+        fn serialize_u32(self, _: u32) -> Result<(), Error> {
+            Err(unsupported($what))
+        }
+        fn serialize_u64(self, _: u64) -> Result<(), Error> {
+            Err(unsupported($what))
+        }
+        fn serialize_f32(self, _: f32) -> Result<(), Error> {
+            Err(unsupported($what))
+        }
+        fn serialize_f64(self, _: f64) -> Result<(), Error> {
+            Err(unsupported($what))
+        }
+        fn serialize_char(self, _: char) -> Result<(), Error> {
+            Err(unsupported($what))
+        }
+        fn serialize_str(self, _: &str) -> Result<(), Error> {
+            Err(unsupported($what))
+        }
+        fn serialize_bytes(self, _: &[u8]) -> Result<(), Error> {
+            Err(unsupported($what))
+        }
+        fn serialize_none(self) -> Result<(), Error> {
+            Err(unsupported($what))
+        }
+        fn serialize_some<T: ?Sized + Serialize>(self, _: &T) -> Result<(), Error> {
+            Err(unsupported($what))
+        }
+        fn serialize_unit(self) -> Result<(), Error> {
+            Err(unsupported($what))
+        }
+        fn serialize_unit_struct(self, _: &'static str) -> Result<(), Error> {
+            Err(unsupported($what))
+        }
+        fn serialize_unit_variant(
+            self,
+            _: &'static str,
+            _: u32,
+            _: &'static str,
+        ) -> Result<(), Error> {
+            Err(unsupported($what))
+        }
+        fn serialize_newtype_variant<T: ?Sized + Serialize>(
+            self,
+            _: &'static str,
+            _: u32,
+            _: &'static str,
+            _: &T,
+        ) -> Result<(), Error> {
+            Err(unsupported($what))
+        }
+        fn serialize_tuple_variant(
+            self,
+            _: &'static str,
+            _: u32,
+            _: &'static str,
+            _: usize,
+        ) -> Result<Self::SerializeTupleVariant, Error> {
+            Err(unsupported($what))
+        }
+        fn serialize_map(self, _: Option<usize>) -> Result<Self::SerializeMap, Error> {
+            Err(unsupported($what))
+        }
+        fn serialize_struct_variant(
+            self,
+            _: &'static str,
+            _: u32,
+            _: &'static str,
+            _: usize,
+        ) -> Result<Self::SerializeStructVariant, Error> {
+            Err(unsupported($what))
+        }
+    };
+}
+
+impl<'a, W: Write> ser::Serializer for DocumentSerializer<'a, W> {
+    type Ok = ();
+    type Error = Error;
+    type SerializeSeq = Self;
+    type SerializeTuple = Self;
+    type SerializeTupleStruct = Self;
+    type SerializeTupleVariant = Impossible<(), Error>;
+    type SerializeMap = Impossible<(), Error>;
+    type SerializeStruct = Impossible<(), Error>;
+    type SerializeStructVariant = Impossible<(), Error>;
+
+    reject_scalars!("a value as an RSV document, expected a sequence of rows");
+
+    fn serialize_newtype_struct<T: ?Sized + Serialize>(
+        self,
+        _: &'static str,
+        value: &T,
+    ) -> Result<(), Error> {
+        value.serialize(self)
+    }
+
+    fn serialize_seq(self, _: Option<usize>) -> Result<Self, Error> {
+        Ok(self)
+    }
+
+    fn serialize_tuple(self, _: usize) -> Result<Self, Error> {
+        Ok(self)
+    }
+
+    fn serialize_tuple_struct(self, _: &'static str, _: usize) -> Result<Self, Error> {
+        Ok(self)
+    }
+
+    fn serialize_struct(self, _: &'static str, _: usize) -> Result<Self::SerializeStruct, Error> {
+        Err(unsupported(
+            "a struct as an RSV document, expected a sequence of rows",
+        ))
+    }
+}
+
+impl<'a, W: Write> ser::SerializeSeq for DocumentSerializer<'a, W> {
+    type Ok = ();
+    type Error = Error;
+
+    fn serialize_element<T: ?Sized + Serialize>(&mut self, value: &T) -> Result<(), Error> {
+        value.serialize(RowSerializer {
+            writer: self.writer,
+        })
+    }
+
+    fn end(self) -> Result<(), Error> {
+        Ok(())
+    }
+}
+
+impl<'a, W: Write> ser::SerializeTuple for DocumentSerializer<'a, W> {
+    type Ok = ();
+    type Error = Error;
+
+    fn serialize_element<T: ?Sized + Serialize>(&mut self, value: &T) -> Result<(), Error> {
+        ser::SerializeSeq::serialize_element(self, value)
+    }
+
+    fn end(self) -> Result<(), Error> {
+        Ok(())
+    }
+}
+
+impl<'a, W: Write> ser::SerializeTupleStruct for DocumentSerializer<'a, W> {
+    type Ok = ();
+    type Error = Error;
+
+    fn serialize_field<T: ?Sized + Serialize>(&mut self, value: &T) -> Result<(), Error> {
+        ser::SerializeSeq::serialize_element(self, value)
+    }
+
+    fn end(self) -> Result<(), Error> {
+        Ok(())
+    }
+}
+
+impl<'a, W: Write> RowSerializer<'a, W> {
+    fn start(self) -> Result<Self, Error> {
+        self.writer.start_row()?;
+        Ok(self)
+    }
+}
+
+impl<'a, W: Write> ser::Serializer for RowSerializer<'a, W> {
+    type Ok = ();
+    type Error = Error;
+    type SerializeSeq = Self;
+    type SerializeTuple = Self;
+    type SerializeTupleStruct = Self;
+    type SerializeTupleVariant = Impossible<(), Error>;
+    type SerializeMap = Impossible<(), Error>;
+    type SerializeStruct = Self;
+    type SerializeStructVariant = Impossible<(), Error>;
+
+    reject_scalars!("a value as an RSV row, expected a struct, tuple or sequence");
+
+    fn serialize_newtype_struct<T: ?Sized + Serialize>(
+        self,
+        _: &'static str,
+        value: &T,
+    ) -> Result<(), Error> {
+        value.serialize(self)
+    }
+
+    fn serialize_seq(self, _: Option<usize>) -> Result<Self, Error> {
+        self.start()
+    }
+
+    fn serialize_tuple(self, _: usize) -> Result<Self, Error> {
+        self.start()
+    }
+
+    fn serialize_tuple_struct(self, _: &'static str, _: usize) -> Result<Self, Error> {
+        self.start()
+    }
+
+    fn serialize_struct(self, _: &'static str, _: usize) -> Result<Self, Error> {
+        self.start()
+    }
+}
+
+impl<'a, W: Write> ser::SerializeSeq for RowSerializer<'a, W> {
+    type Ok = ();
+    type Error = Error;
+
+    fn serialize_element<T: ?Sized + Serialize>(&mut self, value: &T) -> Result<(), Error> {
+        value.serialize(ValueSerializer {
+            writer: self.writer,
+        })
+    }
+
+    fn end(self) -> Result<(), Error> {
+        Ok(())
+    }
+}
+
+impl<'a, W: Write> ser::SerializeTuple for RowSerializer<'a, W> {
+    type Ok = ();
+    type Error = Error;
+
+    fn serialize_element<T: ?Sized + Serialize>(&mut self, value: &T) -> Result<(), Error> {
+        ser::SerializeSeq::serialize_element(self, value)
+    }
+
+    fn end(self) -> Result<(), Error> {
+        Ok(())
+    }
+}
+
+impl<'a, W: Write> ser::SerializeTupleStruct for RowSerializer<'a, W> {
+    type Ok = ();
+    type Error = Error;
+
+    fn serialize_field<T: ?Sized + Serialize>(&mut self, value: &T) -> Result<(), Error> {
+        ser::SerializeSeq::serialize_element(self, value)
+    }
+
+    fn end(self) -> Result<(), Error> {
+        Ok(())
+    }
+}
+
+impl<'a, W: Write> ser::SerializeStruct for RowSerializer<'a, W> {
+    type Ok = ();
+    type Error = Error;
+
+    fn serialize_field<T: ?Sized + Serialize>(
+        &mut self,
+        _: &'static str,
+        value: &T,
+    ) -> Result<(), Error> {
+        ser::SerializeSeq::serialize_element(self, value)
+    }
+
+    fn end(self) -> Result<(), Error> {
+        Ok(())
+    }
+}
+
+impl<'a, W: Write> ValueSerializer<'a, W> {
+    fn push_display(self, value: impl std::fmt::Display) -> Result<(), Error> {
+        self.writer.push_str(&value.to_string())?;
+        Ok(())
+    }
+}
+
+impl<'a, W: Write> ser::Serializer for ValueSerializer<'a, W> {
+    type Ok = ();
+    type Error = Error;
+    type SerializeSeq = Impossible<(), Error>;
+    type SerializeTuple = Impossible<(), Error>;
+    type SerializeTupleStruct = Impossible<(), Error>;
+    type SerializeTupleVariant = Impossible<(), Error>;
+    type SerializeMap = Impossible<(), Error>;
+    type SerializeStruct = Impossible<(), Error>;
+    type SerializeStructVariant = Impossible<(), Error>;
+
+    fn serialize_bool(self, value: bool) -> Result<(), Error> {
+        self.push_display(value)
+    }
+
+    fn serialize_i8(self, value: i8) -> Result<(), Error> {
+        self.push_display(value)
+    }
+
+    fn serialize_i16(self, value: i16) -> Result<(), Error> {
+        self.push_display(value)
+    }
+
+    fn serialize_i32(self, value: i32) -> Result<(), Error> {
+        self.push_display(value)
+    }
+
+    fn serialize_i64(self, value: i64) -> Result<(), Error> {
+        self.push_display(value)
+    }
+
+    fn serialize_i128(self, value: i128) -> Result<(), Error> {
+        self.push_display(value)
+    }
+
+    fn serialize_u8(self, value: u8) -> Result<(), Error> {
+        self.push_display(value)
+    }
+
+    fn serialize_u16(self, value: u16) -> Result<(), Error> {
+        self.push_display(value)
+    }
+
+    fn serialize_u32(self, value: u32) -> Result<(), Error> {
+        self.push_display(value)
+    }
+
+    fn serialize_u64(self, value: u64) -> Result<(), Error> {
+        self.push_display(value)
+    }
+
+    fn serialize_u128(self, value: u128) -> Result<(), Error> {
+        self.push_display(value)
+    }
+
+    fn serialize_f32(self, value: f32) -> Result<(), Error> {
+        self.push_display(value)
+    }
+
+    fn serialize_f64(self, value: f64) -> Result<(), Error> {
+        self.push_display(value)
+    }
+
+    fn serialize_char(self, value: char) -> Result<(), Error> {
+        self.push_display(value)
+    }
+
+    fn serialize_str(self, value: &str) -> Result<(), Error> {
+        self.writer.push_str(value)?;
+        Ok(())
+    }
+
+    fn serialize_bytes(self, value: &[u8]) -> Result<(), Error> {
+        let value = std::str::from_utf8(value)
+            .map_err(|_| unsupported("a byte array containing invalid UTF-8"))?;
+        self.serialize_str(value)
+    }
+
+    fn serialize_none(self) -> Result<(), Error> {
+        self.writer.push_null()?;
+        Ok(())
+    }
+
+    fn serialize_some<T: ?Sized + Serialize>(self, value: &T) -> Result<(), Error> {
+        value.serialize(self)
+    }
+
+    fn serialize_unit(self) -> Result<(), Error> {
+        self.serialize_none()
+    }
+
+    fn serialize_unit_struct(self, _: &'static str) -> Result<(), Error> {
+        self.serialize_none()
+    }
+
+    fn serialize_unit_variant(
+        self,
+        _: &'static str,
+        _: u32,
+        variant: &'static str,
+    ) -> Result<(), Error> {
+        self.serialize_str(variant)
+    }
+
+    fn serialize_newtype_struct<T: ?Sized + Serialize>(
+        self,
+        _: &'static str,
+        value: &T,
+    ) -> Result<(), Error> {
+        value.serialize(self)
+    }
+
+    fn serialize_newtype_variant<T: ?Sized + Serialize>(
+        self,
+        _: &'static str,
+        _: u32,
+        _: &'static str,
+        _: &T,
+    ) -> Result<(), Error> {
+        Err(unsupported("an enum variant with data as an RSV value"))
+    }
+
+    fn serialize_seq(self, _: Option<usize>) -> Result<Self::SerializeSeq, Error> {
+        Err(unsupported("a nested sequence as an RSV value"))
+    }
+
+    fn serialize_tuple(self, _: usize) -> Result<Self::SerializeTuple, Error> {
+        Err(unsupported("a nested tuple as an RSV value"))
+    }
+
+    fn serialize_tuple_struct(
+        self,
+        _: &'static str,
+        _: usize,
+    ) -> Result<Self::SerializeTupleStruct, Error> {
+        Err(unsupported("a nested tuple struct as an RSV value"))
+    }
+
+    fn serialize_tuple_variant(
+        self,
+        _: &'static str,
+        _: u32,
+        _: &'static str,
+        _: usize,
+    ) -> Result<Self::SerializeTupleVariant, Error> {
+        Err(unsupported("an enum variant with data as an RSV value"))
+    }
+
+    fn serialize_map(self, _: Option<usize>) -> Result<Self::SerializeMap, Error> {
+        Err(unsupported("a nested map as an RSV value"))
+    }
+
+    fn serialize_struct(self, _: &'static str, _: usize) -> Result<Self::SerializeStruct, Error> {
+        Err(unsupported("a nested struct as an RSV value"))
+    }
+
+    fn serialize_struct_variant(
+        self,
+        _: &'static str,
+        _: u32,
+        _: &'static str,
+        _: usize,
+    ) -> Result<Self::SerializeStructVariant, Error> {
+        Err(unsupported("an enum variant with data as an RSV value"))
+    }
+}
@@ -0,0 +1,255 @@
+use crate::encode::format_value;
+use crate::{check_bytes, IntoRsvRow, IntoRsvValue, WriteError, END_ROW, END_VALUE, NULL_VALUE};
+
+/// Writes an RSV document into a caller-provided buffer, without allocating.
+///
+/// This mirrors `RsvWriter`, except that every operation returns `WriteError::BufferFull` if the
+/// buffer is too small, in which case nothing is written and the writer can still be finished.
+/// Pushing a value before starting a row returns `WriteError::RowNotStarted`, rather than panicking.
+/// Space for the current row's terminator is reserved when the row is started, so `finish` cannot
+/// fail. Use `encoded_len` to size the buffer in advance.
+///
+/// # Example:
+/// ```
+/// use librsv::{RsvSliceWriter, WriteError};
+///
+/// let mut buffer = [0; 8];
+/// let mut writer = RsvSliceWriter::new(&mut buffer);
+/// writer.start_row()?;
+/// writer.push_str("Hello")?;
+/// assert!(matches!(writer.push_null(), Err(WriteError::BufferFull { .. })));
+///
+/// assert_eq!(writer.finish(), b"Hello\xFF\xFD");
+/// # Ok::<(), WriteError>(())
+/// ```
+pub struct RsvSliceWriter<'a> {
+    buffer: &'a mut [u8],
+    len: usize,
+    started_row: bool,
+}
+
+impl<'a> RsvSliceWriter<'a> {
+    /// Creates a new `RsvSliceWriter` which writes to the start of the given buffer.
+    pub fn new(buffer: &'a mut [u8]) -> Self {
+        Self {
+            buffer,
+            len: 0,
+            started_row: false,
+        }
+    }
+
+    /// The number of bytes written so far, not including the current row's terminator.
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    /// Whether nothing has been written yet.
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    /// Begins a new row.
+    ///
+    /// This must be called before pushing any values.
+    pub fn start_row(&mut self) -> Result<(), WriteError> {
+        // The previous row's terminator was already reserved, so only the new one needs space
+        self.reserve(1)?;
+        if self.started_row {
+            self.write(&[END_ROW]);
+        }
+        self.started_row = true;
+        Ok(())
+    }
+
+    /// Pushes a value to the current row.
+    pub fn push(&mut self, value: Option<&str>) -> Result<(), WriteError> {
+        self.push_bytes(value.map(str::as_bytes))
+    }
+
+    /// Pushes a value of raw bytes to the current row, which need not be valid UTF-8.
+    ///
+    /// Values containing reserved bytes are rejected in the same way as by `RsvWriter::push_bytes`.
+    pub fn push_bytes(&mut self, value: Option<&[u8]>) -> Result<(), WriteError> {
+        if let Some(bytes) = value {
+            check_bytes(bytes)?;
+        }
+        if !self.started_row {
+            return Err(WriteError::RowNotStarted);
+        }
+        match value {
+            Some(bytes) => {
+                self.reserve(bytes.len() + 1)?;
+                self.write(bytes);
+            }
+            None => {
+                self.reserve(2)?;
+                self.write(&[NULL_VALUE]);
+            }
+        }
+        self.write(&[END_VALUE]);
+        Ok(())
+    }
+
+    /// Pushes a string value to the current row.
+    pub fn push_str(&mut self, value: &str) -> Result<(), WriteError> {
+        self.push(Some(value))
+    }
+
+    /// Pushes an empty value to the current row.
+    pub fn push_null(&mut self) -> Result<(), WriteError> {
+        self.push(None)
+    }
+
+    /// Finishes writing and returns the encoded part of the buffer.
+    pub fn finish(self) -> &'a [u8] {
+        let mut len = self.len;
+        if self.started_row {
+            self.buffer[len] = END_ROW;
+            len += 1;
+        }
+        &self.buffer[..len]
+    }
+
+    /// Checks that `needed` more bytes fit, alongside the reserved terminator of the current row.
+    fn reserve(&self, needed: usize) -> Result<(), WriteError> {
+        let available = self.buffer.len() - self.len - usize::from(self.started_row);
+        match needed <= available {
+            true => Ok(()),
+            false => Err(WriteError::BufferFull { needed, available }),
+        }
+    }
+
+    fn write(&mut self, bytes: &[u8]) {
+        self.buffer[self.len..self.len + bytes.len()].copy_from_slice(bytes);
+        self.len += bytes.len();
+    }
+}
+
+/// Calculates the exact length of the document `encode_rsv` would produce for the given rows.
+///
+/// The rows can be of any of the types accepted by `encode_rsv`. This does not allocate, so it can
+/// be used to size the buffer of an `RsvSliceWriter`.
+///
+/// # Example:
+/// ```
+/// let rows = [[Some("Hello"), None], [Some("world"), Some("")]];
+///
+/// assert_eq!(librsv::encoded_len(&rows), 17);
+/// ```
+pub fn encoded_len<T, R, M>(rows: T) -> usize
+where
+    T: IntoIterator<Item = R>,
+    R: IntoRsvRow<M>,
+{
+    let mut len = 0;
+    for row in rows {
+        for value in row.into_rsv_row() {
+            match value.into_rsv_value() {
+                Some(value) => format_value(value, |bytes| len += bytes.len()),
+                None => len += 1,
+            }
+            len += 1;
+        }
+        len += 1;
+    }
+    len
+}
+
+#[cfg(all(test, feature = "alloc"))]
+mod tests {
+    use super::*;
+    use crate::{encode_rsv, RsvWriter};
+    use alloc::string::String;
+    use alloc::vec;
+    use alloc::vec::Vec;
+
+    #[test]
+    fn matches_rsv_writer() {
+        let rows = vec![
+            vec![Some("Hello"), None, Some("wörld")],
+            vec![],
+            vec![Some("")],
+        ];
+        let encoded = encode_rsv(&rows);
+        assert_eq!(encoded_len(&rows), encoded.len());
+        assert_eq!(encoded_len(Vec::<Vec<Option<&str>>>::new()), 0);
+
+        let mut buffer = vec![0; encoded.len()];
+        let mut writer = RsvSliceWriter::new(&mut buffer);
+        for row in &rows {
+            writer.start_row().unwrap();
+            for &value in row {
+                writer.push(value).unwrap();
+            }
+        }
+        assert_eq!(writer.finish(), &encoded[..]);
+
+        let mut a = RsvWriter::new();
+        a.start_row();
+        a.push_bytes(Some(b"\xE9")).unwrap();
+        let mut buffer = [0; 8];
+        let mut b = RsvSliceWriter::new(&mut buffer);
+        b.start_row().unwrap();
+        b.push_bytes(Some(b"\xE9")).unwrap();
+        assert_eq!(
+            b.push_bytes(Some(b"a\xFE")),
+            Err(WriteError::ReservedByte {
+                byte: 0xFE,
+                position: 1
+            })
+        );
+        assert_eq!(b.finish(), &a.finish()[..]);
+    }
+
+    #[test]
+    fn encoded_len_without_options() {
+        let strings = vec![vec![String::from("a"), String::from("wörld")], vec![]];
+        assert_eq!(encoded_len(&strings), encode_rsv(&strings).len());
+        let numbers = [[1.5, -2.0], [10.0, 0.25]];
+        assert_eq!(encoded_len(numbers), encode_rsv(numbers).len());
+        let rows = || (1..=3).map(|i| (0..i).map(|j| j * 100));
+        assert_eq!(encoded_len(rows()), encode_rsv(rows()).len());
+        let values = [Some("Hello"), None];
+        let slices: &[&[Option<&str>]] = &[&values, &values[1..]];
+        assert_eq!(encoded_len(slices), encode_rsv(slices).len());
+    }
+
+    #[test]
+    fn buffer_full() {
+        let mut buffer = [0; 6];
+        assert!(RsvSliceWriter::new(&mut buffer).finish().is_empty());
+
+        let mut writer = RsvSliceWriter::new(&mut buffer);
+        writer.start_row().unwrap();
+        writer.push_str("ab").unwrap();
+        assert_eq!(writer.len(), 3);
+        assert_eq!(
+            writer.push_str("cd"),
+            Err(WriteError::BufferFull {
+                needed: 3,
+                available: 2
+            })
+        );
+        writer.push_null().unwrap();
+        // The row terminator is reserved, so a new row cannot be started
+        assert_eq!(
+            writer.start_row(),
+            Err(WriteError::BufferFull {
+                needed: 1,
+                available: 0
+            })
+        );
+        assert_eq!(writer.finish(), b"ab\xFF\xFE\xFF\xFD");
+
+        let mut empty = [];
+        let mut writer = RsvSliceWriter::new(&mut empty);
+        assert_eq!(writer.push_null(), Err(WriteError::RowNotStarted));
+        // A value with reserved bytes is reported as such, as by `RsvWriter::push_bytes`
+        assert!(matches!(
+            writer.push_bytes(Some(b"\xFF")),
+            Err(WriteError::ReservedByte { .. })
+        ));
+        assert!(writer.start_row().is_err());
+        assert!(writer.finish().is_empty());
+    }
+}
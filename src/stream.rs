@@ -0,0 +1,500 @@
+use crate::{
+    check_bytes, Error, IntoRsvRow, IntoRsvValue, RsvRow, WriteError, END_ROW, END_VALUE,
+    NULL_VALUE,
+};
+use std::io::{self, BufRead, Write};
+
+/// Writes an RSV document to any `std::io::Write` sink, such as a file or socket.
+///
+/// Values are written straight through to the sink as they are pushed, so the document is never
+/// held in memory. For unbuffered sinks such as `File`, wrapping them in a `BufWriter` is recommended.
+///
+/// The final row terminator is written when `finish` is called, or when the writer is dropped.
+/// Errors encountered while dropping are ignored, so `finish` should be preferred.
+///
+/// # Example:
+/// ```
+/// use librsv::RsvStreamWriter;
+///
+/// let mut writer = RsvStreamWriter::new(Vec::new());
+/// writer.start_row()?;
+/// writer.push_str("Hello")?;
+/// writer.push_null()?;
+/// let buffer = writer.finish()?;
+///
+/// assert_eq!(&buffer, b"Hello\xFF\xFE\xFF\xFD");
+/// # Ok::<(), std::io::Error>(())
+/// ```
+pub struct RsvStreamWriter<W: Write> {
+    inner: Option<W>,
+    started_row: bool,
+}
+
+impl<W: Write> RsvStreamWriter<W> {
+    /// Creates a new `RsvStreamWriter` which writes to the given sink.
+    pub fn new(inner: W) -> Self {
+        Self {
+            inner: Some(inner),
+            started_row: false,
+        }
+    }
+
+    /// Returns a reference to the underlying sink.
+    pub fn get_ref(&self) -> &W {
+        self.inner.as_ref().expect("writer has already finished")
+    }
+
+    /// Returns a mutable reference to the underlying sink.
+    ///
+    /// Writing directly to the sink may corrupt the RSV document.
+    pub fn get_mut(&mut self) -> &mut W {
+        self.inner.as_mut().expect("writer has already finished")
+    }
+
+    /// Begins a new row.
+    ///
+    /// This must be called before pushing any values.
+    pub fn start_row(&mut self) -> io::Result<()> {
+        if self.started_row {
+            self.get_mut().write_all(&[END_ROW])?;
+        }
+        self.started_row = true;
+        Ok(())
+    }
+
+    /// Pushes a value to the current row.
+    ///
+    /// Returns an error of kind `InvalidInput` wrapping `WriteError::RowNotStarted` if no row has
+    /// been started, in which case nothing is written.
+    pub fn push(&mut self, value: Option<&str>) -> io::Result<()> {
+        self.check_row()?;
+        let inner = self.get_mut();
+        match value {
+            Some(str) => inner.write_all(str.as_bytes())?,
+            None => inner.write_all(&[NULL_VALUE])?,
+        }
+        inner.write_all(&[END_VALUE])
+    }
+
+    /// Pushes a value of raw bytes to the current row, which need not be valid UTF-8.
+    ///
+    /// Values containing the bytes `0xFD`, `0xFE` or `0xFF` cannot be represented in RSV, so are
+    /// rejected with an error of kind `InvalidInput` wrapping a `WriteError`, in which case nothing is written.
+    /// Like `push`, this also returns such an error if there is no row to push to.
+    pub fn push_bytes(&mut self, value: Option<&[u8]>) -> io::Result<()> {
+        if let Some(bytes) = value {
+            check_bytes(bytes).map_err(invalid_input)?;
+        }
+        self.check_row()?;
+        let inner = self.get_mut();
+        match value {
+            Some(bytes) => inner.write_all(bytes)?,
+            None => inner.write_all(&[NULL_VALUE])?,
+        }
+        inner.write_all(&[END_VALUE])
+    }
+
+    /// Pushes a string value to the current row.
+    pub fn push_str(&mut self, value: &str) -> io::Result<()> {
+        self.push(Some(value))
+    }
+
+    /// Pushes an empty value to the current row.
+    pub fn push_null(&mut self) -> io::Result<()> {
+        self.push(None)
+    }
+
+    /// Writes a whole row, as with `RsvWriter::write_row`.
+    ///
+    /// Each value is written as soon as the iterator produces it, so rows can be streamed straight
+    /// from a source such as a database cursor.
+    pub fn write_row<R, M>(&mut self, row: R) -> io::Result<()>
+    where
+        R: IntoRsvRow<M>,
+    {
+        self.start_row()?;
+        let inner = self.get_mut();
+        for value in row.into_rsv_row() {
+            match value.into_rsv_value() {
+                Some(value) => write!(inner, "{}", value)?,
+                None => inner.write_all(&[NULL_VALUE])?,
+            }
+            inner.write_all(&[END_VALUE])?;
+        }
+        Ok(())
+    }
+
+    /// Writes each of the given rows, as with `write_row`.
+    pub fn write_rows<T, R, M>(&mut self, rows: T) -> io::Result<()>
+    where
+        T: IntoIterator<Item = R>,
+        R: IntoRsvRow<M>,
+    {
+        for row in rows {
+            self.write_row(row)?;
+        }
+        Ok(())
+    }
+
+    /// Flushes the underlying sink.
+    ///
+    /// This does not terminate the current row.
+    pub fn flush(&mut self) -> io::Result<()> {
+        self.get_mut().flush()
+    }
+
+    /// Checks that there is a row to push values to.
+    fn check_row(&self) -> io::Result<()> {
+        match self.started_row {
+            true => Ok(()),
+            false => Err(invalid_input(WriteError::RowNotStarted)),
+        }
+    }
+
+    /// Finishes writing, flushes the sink, and returns it.
+    pub fn finish(mut self) -> io::Result<W> {
+        self.end()?;
+        Ok(self.inner.take().expect("writer has already finished"))
+    }
+
+    /// Terminates the current row, if any, and flushes the sink.
+    fn end(&mut self) -> io::Result<()> {
+        if std::mem::take(&mut self.started_row) {
+            self.get_mut().write_all(&[END_ROW])?;
+        }
+        self.flush()
+    }
+}
+
+impl<W: Write> Drop for RsvStreamWriter<W> {
+    fn drop(&mut self) {
+        if self.inner.is_some() {
+            let _ = self.end();
+        }
+    }
+}
+
+/// Wraps a `WriteError` from a streaming writer in an error of kind `InvalidInput`.
+pub(crate) fn invalid_input(err: WriteError) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidInput, err)
+}
+
+/// Reads an RSV document incrementally from any `std::io::BufRead` source, such as a buffered file.
+///
+/// Rows can either be borrowed from an internal buffer which is reused between rows, using `next_row`,
+/// or read as owned values by using the reader as an iterator.
+///
+/// # Example:
+/// ```
+/// use librsv::RsvStreamReader;
+///
+/// let input: &[u8] = b"Hello\xFFworld\xFF\xFD\xFE\xFF\xFD";
+/// let mut reader = RsvStreamReader::new(input);
+///
+/// while let Some(row) = reader.next_row()? {
+///     for value in row.values() {
+///         println!("{:?}", value?);
+///     }
+/// }
+/// # Ok::<(), librsv::Error>(())
+/// ```
+pub struct RsvStreamReader<R: BufRead> {
+    inner: R,
+    buffer: Vec<u8>,
+    offset: usize,
+    index: usize,
+}
+
+impl<R: BufRead> RsvStreamReader<R> {
+    /// Creates a new `RsvStreamReader` which reads from the given source.
+    pub fn new(inner: R) -> Self {
+        Self {
+            inner,
+            buffer: Vec::new(),
+            offset: 0,
+            index: 0,
+        }
+    }
+
+    /// Reads the next row, returning `None` once the end of the stream is reached.
+    ///
+    /// The returned row borrows from a buffer which is reused by subsequent calls.
+    /// If the stream ends part way through a row, `Error::UnterminatedRow` is returned.
+    pub fn next_row(&mut self) -> Result<Option<RsvRow<'_>>, Error> {
+        if self.buffer.last() == Some(&END_ROW) {
+            self.buffer.clear();
+        }
+        // Partial rows are kept in the buffer, so no data is lost if reading fails and is retried
+        self.inner.read_until(END_ROW, &mut self.buffer)?;
+        if self.buffer.is_empty() {
+            return Ok(None);
+        }
+        let (offset, index) = (self.offset, self.index);
+        self.offset += self.buffer.len();
+        self.index += 1;
+        if self.buffer.last() != Some(&END_ROW) {
+            self.buffer.clear();
+            return Err(Error::UnterminatedRow { offset, row: index });
+        }
+        let row = &self.buffer[..self.buffer.len() - 1];
+        Ok(Some(RsvRow::at(row, offset, index)))
+    }
+
+    /// Reads the values of the next row into `buf`, replacing its contents, and returns `false`
+    /// once the end of the stream is reached.
+    ///
+    /// The strings already in `buf` are overwritten in place, so once `buf` has grown large enough,
+    /// reading rows of a similar shape does not allocate. If the row is invalid, `buf` is left empty.
+    ///
+    /// # Example:
+    /// ```
+    /// let mut reader = librsv::RsvStreamReader::new(&b"a\xFFb\xFF\xFD\xFE\xFF\xFD"[..]);
+    /// let mut values = Vec::new();
+    ///
+    /// while reader.read_row_into(&mut values)? {
+    ///     println!("{:?}", values);
+    /// }
+    /// # Ok::<(), librsv::Error>(())
+    /// ```
+    pub fn read_row_into(&mut self, buf: &mut Vec<Option<String>>) -> Result<bool, Error> {
+        let row = match self.next_row() {
+            Ok(Some(row)) => row,
+            Ok(None) => {
+                buf.clear();
+                return Ok(false);
+            }
+            Err(err) => {
+                buf.clear();
+                return Err(err);
+            }
+        };
+        let mut len = 0;
+        for value in row.values() {
+            let value = match value {
+                Ok(value) => value,
+                Err(err) => {
+                    buf.clear();
+                    return Err(err);
+                }
+            };
+            match (buf.get_mut(len), value) {
+                (Some(Some(existing)), Some(value)) => {
+                    existing.clear();
+                    existing.push_str(value);
+                }
+                (Some(existing), value) => *existing = value.map(String::from),
+                (None, value) => buf.push(value.map(String::from)),
+            }
+            len += 1;
+        }
+        buf.truncate(len);
+        Ok(true)
+    }
+
+    /// Returns a reference to the underlying source.
+    pub fn get_ref(&self) -> &R {
+        &self.inner
+    }
+
+    /// Returns a mutable reference to the underlying source.
+    ///
+    /// Reading directly from the source may cause rows to be skipped or truncated.
+    pub fn get_mut(&mut self) -> &mut R {
+        &mut self.inner
+    }
+
+    /// Consumes the reader, returning the underlying source.
+    pub fn into_inner(self) -> R {
+        self.inner
+    }
+}
+
+impl<R: BufRead> Iterator for RsvStreamReader<R> {
+    type Item = Result<Vec<Option<String>>, Error>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let row = match self.next_row() {
+            Ok(row) => row?,
+            Err(err) => return Some(Err(err)),
+        };
+        Some(
+            row.values()
+                .map(|v| v.map(|v| v.map(|v| v.to_string())))
+                .collect(),
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{decode_rsv, RsvWriter};
+
+    #[test]
+    fn matches_rsv_writer() {
+        let mut a = RsvWriter::new();
+        let mut b = RsvStreamWriter::new(Vec::new());
+
+        a.start_row();
+        a.push_str("Hello");
+        a.push_null();
+        a.start_row();
+        a.start_row();
+        a.push_str("");
+
+        b.start_row().unwrap();
+        b.push_str("Hello").unwrap();
+        b.push_null().unwrap();
+        b.start_row().unwrap();
+        b.start_row().unwrap();
+        b.push_str("").unwrap();
+
+        let rows = || vec![vec![Some(Box::<str>::from("x")), None], vec![]];
+        a.write_rows(rows());
+        b.write_rows(rows()).unwrap();
+
+        assert_eq!(a.finish(), b.finish().unwrap());
+    }
+
+    #[test]
+    fn rejects_reserved_bytes() {
+        let mut writer = RsvStreamWriter::new(Vec::new());
+        writer.start_row().unwrap();
+        writer.push_bytes(Some(b"\xE9")).unwrap();
+        let err = writer.push_bytes(Some(b"a\xFEb")).unwrap_err();
+        assert_eq!(err.kind(), io::ErrorKind::InvalidInput);
+        assert_eq!(&writer.finish().unwrap(), b"\xE9\xFF\xFD");
+    }
+
+    #[test]
+    fn row_not_started() {
+        let mut writer = RsvStreamWriter::new(Vec::new());
+        let err = writer.push_str("a").unwrap_err();
+        assert_eq!(err.kind(), io::ErrorKind::InvalidInput);
+        assert_eq!(
+            err.get_ref().unwrap().downcast_ref(),
+            Some(&WriteError::RowNotStarted)
+        );
+        let err = writer.push_bytes(Some(b"\xFF")).unwrap_err();
+        assert_eq!(
+            err.get_ref().unwrap().downcast_ref(),
+            Some(&WriteError::ReservedByte {
+                byte: 0xFF,
+                position: 0
+            })
+        );
+        assert!(writer.push_bytes(None).is_err());
+        assert!(writer.finish().unwrap().is_empty());
+    }
+
+    #[test]
+    fn terminates_row_on_drop() {
+        let mut buffer = Vec::new();
+        {
+            let mut writer = RsvStreamWriter::new(&mut buffer);
+            writer.start_row().unwrap();
+            writer.push_str("a").unwrap();
+        }
+        assert_eq!(&buffer, b"a\xFF\xFD");
+    }
+
+    #[test]
+    fn empty_document() {
+        let writer = RsvStreamWriter::new(Vec::new());
+        assert!(writer.finish().unwrap().is_empty());
+    }
+
+    #[test]
+    fn reads_rows_incrementally() {
+        let data = b"Hello\xFFworld\xFF\xFD\xFD\xFE\xFF\xFF\xFD";
+        // A tiny buffer capacity forces rows to span multiple reads
+        let input = io::BufReader::with_capacity(3, &data[..]);
+        let rows = RsvStreamReader::new(input)
+            .collect::<Result<Vec<_>, _>>()
+            .unwrap();
+        assert_eq!(rows, decode_rsv(data).unwrap());
+    }
+
+    #[test]
+    fn reuses_strings() {
+        let data = b"abc\xFFdef\xFF\xFD\xFE\xFFx\xFF\xFDy\xFF\xFD\xC3\xFF\xFD";
+        let mut reader = RsvStreamReader::new(&data[..]);
+        let mut buf = vec![];
+        assert!(reader.read_row_into(&mut buf).unwrap());
+        let second = buf[1].as_ref().unwrap().as_ptr();
+        assert!(reader.read_row_into(&mut buf).unwrap());
+        assert_eq!(buf, vec![None, Some("x".into())]);
+        assert_eq!(buf[1].as_ref().unwrap().as_ptr(), second);
+        assert!(reader.read_row_into(&mut buf).unwrap());
+        assert_eq!(buf, vec![Some("y".into())]);
+        assert!(matches!(
+            reader.read_row_into(&mut buf),
+            Err(Error::BadUTF8 { row: 3, .. })
+        ));
+        assert!(buf.is_empty());
+        assert!(!reader.read_row_into(&mut buf).unwrap());
+    }
+
+    #[test]
+    fn unterminated_row_at_end_of_stream() {
+        let mut reader = RsvStreamReader::new(&b"a\xFF\xFDb\xFF"[..]);
+        assert!(reader.next_row().unwrap().is_some());
+        assert!(matches!(
+            reader.next_row(),
+            Err(Error::UnterminatedRow { offset: 3, row: 1 })
+        ));
+        assert!(reader.next_row().unwrap().is_none());
+    }
+
+    #[test]
+    fn surfaces_io_errors() {
+        struct Failing;
+        impl io::Read for Failing {
+            fn read(&mut self, _: &mut [u8]) -> io::Result<usize> {
+                Err(io::Error::other("boom"))
+            }
+        }
+        let mut reader = RsvStreamReader::new(io::BufReader::new(Failing));
+        let err = reader.next().unwrap().unwrap_err();
+        assert!(matches!(&err, Error::Io(err) if err.kind() == io::ErrorKind::Other));
+        assert_eq!(err.clone(), err);
+        assert_eq!(err, Error::from(io::Error::other("boom")));
+        assert_ne!(err, Error::from(io::Error::other("bang")));
+    }
+
+    #[test]
+    fn resumes_rows_after_io_errors() {
+        // Fails every other read, starting part way through the second row
+        struct Flaky<'a>(&'a [u8], bool);
+        impl io::Read for Flaky<'_> {
+            fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+                self.1 = !self.1;
+                if self.1 {
+                    return self.0.read(buf);
+                }
+                Err(io::ErrorKind::WouldBlock.into())
+            }
+        }
+        let data = b"a\xFF\xFDbcd\xFFe\xFF\xFD\xC3\xFF\xFD";
+        let input = io::BufReader::with_capacity(4, Flaky(data, false));
+        let mut reader = RsvStreamReader::new(input);
+        let mut rows = vec![];
+        let err = loop {
+            match reader.next() {
+                Some(Ok(row)) => rows.push(row),
+                Some(Err(Error::Io(err))) => assert_eq!(err.kind(), io::ErrorKind::WouldBlock),
+                Some(Err(err)) => break err,
+                None => unreachable!(),
+            }
+        };
+        assert_eq!(rows, decode_rsv(&data[..10]).unwrap());
+        assert!(matches!(
+            err,
+            Error::BadUTF8 {
+                offset: 10,
+                row: 2,
+                ..
+            }
+        ));
+    }
+}
@@ -0,0 +1,327 @@
+use crate::{Error, RsvRow};
+use alloc::borrow::Cow;
+use alloc::string::{String, ToString};
+use core::fmt::Display;
+use core::str::FromStr;
+
+/// Why a value could not be converted by `FromValue`.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum ValueError {
+    /// The value was null, but the type cannot represent null.
+    Null,
+    /// The value could not be parsed, for the given reason.
+    Invalid(String),
+}
+
+/// A type which can be parsed from a single RSV value, for use with `RsvRow::decode`.
+///
+/// This is implemented for the primitive types, `String`, `&str` and `Cow<str>`, which reject
+/// nulls, and for `Option<T>`, which maps nulls to `None`.
+pub trait FromValue<'a>: Sized {
+    /// The name of the type, as shown in error messages, such as `"i64"`.
+    const TYPE_NAME: &'static str;
+
+    /// Parses a value, which is `None` if it is null.
+    fn from_value(value: Option<&'a str>) -> Result<Self, ValueError>;
+}
+
+/// A type which can be parsed from a whole RSV row, for use with `RsvRow::decode`.
+///
+/// This is implemented for tuples of up to 12 types implementing `FromValue`.
+pub trait FromRow<'a>: Sized {
+    /// Parses a row.
+    fn from_row(row: &RsvRow<'a>) -> Result<Self, Error>;
+}
+
+fn parse<T: FromStr>(value: Option<&str>) -> Result<T, ValueError>
+where
+    T::Err: Display,
+{
+    value
+        .ok_or(ValueError::Null)?
+        .parse()
+        .map_err(|err: T::Err| ValueError::Invalid(err.to_string()))
+}
+
+macro_rules! from_str_values {
+    ($($ty:ty),*) => {
+        $(
+            impl<'a> FromValue<'a> for $ty {
+                const TYPE_NAME: &'static str = stringify!($ty);
+
+                fn from_value(value: Option<&'a str>) -> Result<Self, ValueError> {
+                    parse(value)
+                }
+            }
+        )*
+    };
+}
+
+from_str_values!(
+    i8, i16, i32, i64, i128, isize, u8, u16, u32, u64, u128, usize, f32, f64, bool, char, String
+);
+
+impl<'a> FromValue<'a> for &'a str {
+    const TYPE_NAME: &'static str = "&str";
+
+    fn from_value(value: Option<&'a str>) -> Result<Self, ValueError> {
+        value.ok_or(ValueError::Null)
+    }
+}
+
+impl<'a> FromValue<'a> for Cow<'a, str> {
+    const TYPE_NAME: &'static str = "Cow<str>";
+
+    fn from_value(value: Option<&'a str>) -> Result<Self, ValueError> {
+        value.map(Cow::Borrowed).ok_or(ValueError::Null)
+    }
+}
+
+impl<'a, T: FromValue<'a>> FromValue<'a> for Option<T> {
+    // Only a non-null value can fail to parse, so it is named after the type of such a value
+    const TYPE_NAME: &'static str = T::TYPE_NAME;
+
+    fn from_value(value: Option<&'a str>) -> Result<Self, ValueError> {
+        value.map(|value| T::from_value(Some(value))).transpose()
+    }
+}
+
+/// Attaches the location of a value, and the type it was being parsed as if known, to a `ValueError`.
+fn locate(
+    err: ValueError,
+    expected: Option<&'static str>,
+    row: &RsvRow<'_>,
+    offset: usize,
+    value: usize,
+) -> Error {
+    match err {
+        ValueError::Null => Error::NullValue {
+            offset,
+            row: row.index(),
+            value,
+            expected,
+        },
+        ValueError::Invalid(message) => Error::ParseValue {
+            offset,
+            row: row.index(),
+            value,
+            expected,
+            message,
+        },
+    }
+}
+
+impl<'a> RsvRow<'a> {
+    /// Returns the value at the given index, or `None` if it is past the end of the row.
+    pub fn get(&self, index: usize) -> Result<Option<Option<&'a str>>, Error> {
+        self.values().nth(index).transpose()
+    }
+
+    /// Returns the value at the given index, along with its byte offset.
+    fn locate(&self, index: usize) -> Result<(usize, Option<&'a str>), Error> {
+        let missing = Error::MissingValue {
+            offset: self.offset(),
+            row: self.index(),
+            value: index,
+        };
+        let (offset, _, value) = self.located_values().nth(index).ok_or(missing)??;
+        Ok((offset, value))
+    }
+
+    /// Parses the value at the given index as a `T`.
+    ///
+    /// Returns an error naming the value if it is missing, null or cannot be parsed. As `T` can be
+    /// any `FromStr` type, which has no name to report, the error does not name it; decode the row
+    /// with a `FromValue` type for errors that do.
+    ///
+    /// # Example:
+    /// ```
+    /// let row = librsv::RsvRow::new(b"42\xFF\xFE\xFF");
+    ///
+    /// assert_eq!(row.get_as::<i64>(0)?, 42);
+    /// assert_eq!(
+    ///     row.get_as::<i64>(1).unwrap_err().to_string(),
+    ///     "row 0, value 1 (at byte 3) is null"
+    /// );
+    /// # Ok::<(), librsv::Error>(())
+    /// ```
+    pub fn get_as<T: FromStr>(&self, index: usize) -> Result<T, Error>
+    where
+        T::Err: Display,
+    {
+        let (offset, value) = self.locate(index)?;
+        parse(value).map_err(|err| locate(err, None, self, offset, index))
+    }
+
+    /// Parses the value at the given index as a `T`, or returns `None` if it is null.
+    pub fn get_opt_as<T: FromStr>(&self, index: usize) -> Result<Option<T>, Error>
+    where
+        T::Err: Display,
+    {
+        let (offset, value) = self.locate(index)?;
+        value
+            .map(|value| parse(Some(value)))
+            .transpose()
+            .map_err(|err| locate(err, None, self, offset, index))
+    }
+
+    /// Parses the whole row, such as into a tuple of `FromValue` types.
+    ///
+    /// The row must have exactly as many values as the tuple has elements.
+    ///
+    /// # Example:
+    /// ```
+    /// let row = librsv::RsvRow::new(b"42\xFF\xFE\xFFHello\xFF");
+    /// let (id, score, name) = row.decode::<(i64, Option<f64>, String)>()?;
+    ///
+    /// assert_eq!((id, score, name.as_str()), (42, None, "Hello"));
+    /// # Ok::<(), librsv::Error>(())
+    /// ```
+    pub fn decode<T: FromRow<'a>>(&self) -> Result<T, Error> {
+        T::from_row(self)
+    }
+}
+
+macro_rules! tuple_from_row {
+    ($len:expr => $($ty:ident)+) => {
+        impl<'a, $($ty: FromValue<'a>),+> FromRow<'a> for ($($ty,)+) {
+            fn from_row(row: &RsvRow<'a>) -> Result<Self, Error> {
+                let mut values = row.located_values();
+                let mut found = 0;
+                let mismatch = |found| Error::RowWidthMismatch {
+                    offset: row.offset(),
+                    row: row.index(),
+                    expected: $len,
+                    found,
+                };
+                let tuple = ($({
+                    let (offset, index, value) = values.next().ok_or_else(|| mismatch(found))??;
+                    found += 1;
+                    $ty::from_value(value).map_err(|err| locate(err, Some($ty::TYPE_NAME), row, offset, index))?
+                },)+);
+                let extra = values.count();
+                if extra > 0 {
+                    return Err(mismatch(found + extra));
+                }
+                Ok(tuple)
+            }
+        }
+    };
+}
+
+tuple_from_row!(1 => A);
+tuple_from_row!(2 => A B);
+tuple_from_row!(3 => A B C);
+tuple_from_row!(4 => A B C D);
+tuple_from_row!(5 => A B C D E);
+tuple_from_row!(6 => A B C D E F);
+tuple_from_row!(7 => A B C D E F G);
+tuple_from_row!(8 => A B C D E F G H);
+tuple_from_row!(9 => A B C D E F G H I);
+tuple_from_row!(10 => A B C D E F G H I J);
+tuple_from_row!(11 => A B C D E F G H I J K);
+tuple_from_row!(12 => A B C D E F G H I J K L);
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::RsvReader;
+
+    fn row(data: &[u8]) -> RsvRow<'_> {
+        RsvReader::new(data).rows().nth(1).unwrap().unwrap()
+    }
+
+    #[test]
+    fn get_as() {
+        let data = b"\xFD7\xFF-1.5\xFF\xFE\xFFx\xFF\xFD";
+        let row = row(data);
+        assert_eq!(row.get(1).unwrap(), Some(Some("-1.5")));
+        assert_eq!(row.get(4).unwrap(), None);
+        assert_eq!(row.get_as::<u8>(0).unwrap(), 7);
+        assert_eq!(row.get_as::<f64>(1).unwrap(), -1.5);
+        assert_eq!(row.get_opt_as::<f64>(2).unwrap(), None);
+        assert_eq!(row.get_opt_as::<char>(3).unwrap(), Some('x'));
+
+        assert!(matches!(
+            row.get_as::<i32>(2),
+            Err(Error::NullValue {
+                offset: 8,
+                row: 1,
+                value: 2,
+                expected: None
+            })
+        ));
+        assert_eq!(
+            row.get_opt_as::<i32>(1).unwrap_err().to_string(),
+            "row 1, value 1 (at byte 3) could not be parsed: invalid digit found in string"
+        );
+        assert!(matches!(
+            row.get_as::<i32>(4),
+            Err(Error::MissingValue {
+                offset: 1,
+                row: 1,
+                value: 4
+            })
+        ));
+    }
+
+    #[test]
+    fn decode_tuples() {
+        let data = b"\xFD7\xFF-1.5\xFF\xFE\xFFx\xFF\xFD";
+        let row = row(data);
+        let (a, b, c, d) = row.decode::<(u8, f32, Option<bool>, &str)>().unwrap();
+        assert_eq!((a, b, c, d), (7, -1.5, None, "x"));
+        let (a, b, c, d) = row
+            .decode::<(String, Cow<str>, Option<String>, Option<char>)>()
+            .unwrap();
+        assert_eq!(
+            (a.as_str(), b.as_ref(), c, d),
+            ("7", "-1.5", None, Some('x'))
+        );
+
+        assert!(matches!(
+            row.decode::<(u8, f32, bool, &str)>(),
+            Err(Error::NullValue {
+                value: 2,
+                expected: Some("bool"),
+                ..
+            })
+        ));
+        assert!(matches!(
+            row.decode::<(u8, u8, Option<u8>, char)>(),
+            Err(Error::ParseValue {
+                value: 1,
+                expected: Some("u8"),
+                ..
+            })
+        ));
+        assert_eq!(
+            row.decode::<(u8, Option<u16>, Option<u8>, char)>()
+                .unwrap_err()
+                .to_string(),
+            "row 1, value 1 (at byte 3) could not be parsed as u16: invalid digit found in string"
+        );
+        assert_eq!(
+            row.decode::<(u8, f32, String, char)>()
+                .unwrap_err()
+                .to_string(),
+            "row 1, value 2 (at byte 8) is null, expected String"
+        );
+        assert!(matches!(
+            row.decode::<(u8, f32)>(),
+            Err(Error::RowWidthMismatch {
+                expected: 2,
+                found: 4,
+                ..
+            })
+        ));
+        assert!(matches!(
+            row.decode::<(u8, f32, Option<u8>, char, u8)>(),
+            Err(Error::RowWidthMismatch {
+                expected: 5,
+                found: 4,
+                ..
+            })
+        ));
+    }
+}
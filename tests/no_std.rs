@@ -0,0 +1,120 @@
+//! Checks that the crate works without the standard library.
+//!
+//! Run with `cargo test --no-default-features --test no_std`, with or without `--features alloc`.
+//! This file is itself `no_std`, so it only compiles if everything it uses comes from `core`, and
+//! from `alloc` when that feature is enabled.
+
+#![no_std]
+
+#[cfg(feature = "alloc")]
+extern crate alloc;
+
+#[cfg(feature = "alloc")]
+use alloc::string::String;
+#[cfg(feature = "alloc")]
+use alloc::vec;
+#[cfg(feature = "alloc")]
+use alloc::vec::Vec;
+#[cfg(feature = "alloc")]
+use librsv::{decode_rsv, decode_rsv_borrowed, encode_rsv, RsvWriter};
+use librsv::{encoded_len, Error, RsvReader, RsvRow, RsvSliceWriter};
+
+#[test]
+fn reader_without_alloc() {
+    let buffer = b"Hello\xFF\xFE\xFF\xFD\xFD\xE9\xFF\xFDx";
+    let mut rows = RsvReader::new(buffer).rows();
+
+    let row = rows.next().unwrap().unwrap();
+    let mut values = row.values();
+    assert_eq!(values.next().unwrap().unwrap(), Some("Hello"));
+    assert_eq!(values.next().unwrap().unwrap(), None);
+    assert!(values.next().is_none());
+
+    assert!(rows.next().unwrap().unwrap().values().next().is_none());
+    assert!(matches!(
+        rows.next().unwrap().unwrap().values().next(),
+        Some(Err(Error::BadUTF8 { offset: 10, .. }))
+    ));
+    assert!(matches!(
+        rows.next(),
+        Some(Err(Error::UnterminatedRow { offset: 13, row: 3 }))
+    ));
+
+    let row = RsvRow::new(b"a\xFF\xFE\xFF");
+    assert_eq!(row.values().count(), 2);
+}
+
+#[test]
+fn slice_writer_without_alloc() {
+    let rows = [[Some("Hello"), None]];
+    let mut buffer = [0; 16];
+    let mut writer = RsvSliceWriter::new(&mut buffer[..encoded_len(rows)]);
+    writer.start_row().unwrap();
+    writer.push_str("Hello").unwrap();
+    writer.push_null().unwrap();
+    assert!(writer.push_null().is_err());
+    assert_eq!(writer.finish(), b"Hello\xFF\xFE\xFF\xFD");
+}
+
+#[test]
+#[cfg(feature = "alloc")]
+fn reader_and_writer() {
+    let mut writer = RsvWriter::new();
+    writer.start_row();
+    writer.push_str("Hello");
+    writer.push_null();
+    writer.start_row();
+    writer.push_bytes(Some(b"\xE9")).unwrap();
+    let buffer = writer.finish();
+    assert_eq!(&buffer, b"Hello\xFF\xFE\xFF\xFD\xE9\xFF\xFD");
+
+    let reader = RsvReader::new(&buffer);
+    let mut rows = reader.rows();
+    let values = rows.next().unwrap().unwrap().values();
+    assert_eq!(
+        values.collect::<Result<Vec<_>, _>>().unwrap(),
+        [Some("Hello"), None]
+    );
+    assert!(matches!(
+        rows.next().unwrap().unwrap().values().next(),
+        Some(Err(Error::BadUTF8 { offset: 9, .. }))
+    ));
+    assert!(rows.next().is_none());
+}
+
+#[test]
+#[cfg(feature = "alloc")]
+fn encode_and_decode() {
+    let data = vec![
+        vec![Some(String::from("Hello")), None],
+        vec![],
+        vec![Some(String::new())],
+    ];
+    let encoded = encode_rsv(&data);
+    assert_eq!(decode_rsv(&encoded).unwrap(), data);
+    assert_eq!(
+        decode_rsv_borrowed(&encoded).unwrap(),
+        vec![vec![Some("Hello"), None], vec![], vec![Some("")]]
+    );
+}
+
+#[test]
+#[cfg(feature = "alloc")]
+fn typed_values() {
+    let row = RsvRow::new(b"42\xFF\xFE\xFFx\xFF");
+    let (id, score, name) = row.decode::<(i64, Option<f64>, &str)>().unwrap();
+    assert_eq!((id, score, name), (42, None, "x"));
+}
+
+#[test]
+#[cfg(feature = "alloc")]
+fn slice_writer() {
+    let rows = [[Some("Hello"), None]];
+    let mut buffer = [0; 16];
+    let mut writer = RsvSliceWriter::new(&mut buffer[..encoded_len(rows)]);
+    writer.start_row().unwrap();
+    writer.push_str("Hello").unwrap();
+    writer.push_null().unwrap();
+    assert!(writer.push_null().is_err());
+    assert_eq!(writer.finish(), &encode_rsv(rows)[..]);
+}